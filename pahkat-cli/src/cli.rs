@@ -33,11 +33,25 @@ pub(crate) enum Args {
     #[structopt(template(SUB_TEMPLATE))]
     Install(command::Install),
     #[structopt(template(SUB_TEMPLATE))]
+    Upgrade(command::Upgrade),
+    #[structopt(template(SUB_TEMPLATE))]
     Uninstall(command::Uninstall),
     #[structopt(template(SUB_TEMPLATE))]
     Status(command::Status),
     #[structopt(template(SUBC_TEMPLATE))]
     Config(command::Config),
+    #[structopt(template(SUB_TEMPLATE))]
+    Recover(command::Recover),
+    #[structopt(template(SUB_TEMPLATE))]
+    Export(command::Export),
+    #[structopt(template(SUB_TEMPLATE))]
+    Import(command::Import),
+    #[structopt(template(SUB_TEMPLATE))]
+    History(command::History),
+    #[structopt(template(SUB_TEMPLATE))]
+    Why(command::Why),
+    #[structopt(template(SUB_TEMPLATE))]
+    Sync(command::Sync),
 }
 
 impl ConfigPath for Args {
@@ -47,9 +61,16 @@ impl ConfigPath for Args {
             Args::Init(x) => x.config_path(),
             Args::Download(x) => x.config_path(),
             Args::Install(x) => x.config_path(),
+            Args::Upgrade(x) => x.config_path(),
             Args::Uninstall(x) => x.config_path(),
             Args::Config(x) => x.config_path(),
             Args::Status(x) => x.config_path(),
+            Args::Recover(x) => x.config_path(),
+            Args::Export(x) => x.config_path(),
+            Args::Import(x) => x.config_path(),
+            Args::History(x) => x.config_path(),
+            Args::Why(x) => x.config_path(),
+            Args::Sync(x) => x.config_path(),
         }
     }
 }
@@ -61,9 +82,16 @@ impl Platform for Args {
             Args::Init(x) => x.platform(),
             Args::Download(x) => x.platform(),
             Args::Install(x) => x.platform(),
+            Args::Upgrade(x) => x.platform(),
             Args::Uninstall(x) => x.platform(),
             Args::Status(x) => x.platform(),
             Args::Config(x) => None,
+            Args::Recover(x) => x.platform(),
+            Args::Export(x) => x.platform(),
+            Args::Import(x) => x.platform(),
+            Args::History(x) => x.platform(),
+            Args::Why(x) => x.platform(),
+            Args::Sync(x) => x.platform(),
         }
     }
 }
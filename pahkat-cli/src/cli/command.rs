@@ -26,8 +26,22 @@ pub struct Download {
 #[derive(Debug, StructOpt)]
 #[structopt(about = "Install packages from configured repositories")]
 pub struct Install {
-    #[structopt(required = true, help = "Packages to install")]
+    #[structopt(
+        required = true,
+        help = "Packages to install, optionally pinned to a release as `id@version`"
+    )]
     pub packages: Vec<String>,
+    #[structopt(long, help = "Show what would be done without changing anything")]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Upgrade installed packages that have newer releases")]
+pub struct Upgrade {
+    #[structopt(long, help = "Show what would be done without changing anything")]
+    pub dry_run: bool,
     #[structopt(flatten)]
     global_opts: super::GlobalOpts,
 }
@@ -64,6 +78,83 @@ pub struct Status {
     global_opts: super::GlobalOpts,
 }
 
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Resume or roll back an interrupted transaction")]
+pub struct Recover {
+    #[structopt(
+        long,
+        conflicts_with_all = &["rollback", "discard"],
+        help = "Complete the actions that did not finish"
+    )]
+    pub resume: bool,
+    #[structopt(
+        long,
+        conflicts_with = "discard",
+        help = "Undo the actions that were started"
+    )]
+    pub rollback: bool,
+    #[structopt(long, help = "Forget the interrupted transaction without changing anything")]
+    pub discard: bool,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Export installed packages to a lockfile")]
+pub struct Export {
+    #[structopt(
+        short,
+        long = "output",
+        help = "Lockfile to write [default: standard output]",
+        parse(from_os_str)
+    )]
+    pub output_path: Option<PathBuf>,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Install exactly the packages listed in a lockfile")]
+pub struct Import {
+    #[structopt(help = "Lockfile to import", parse(from_os_str))]
+    pub lockfile_path: PathBuf,
+    #[structopt(long, help = "Show what would be done without changing anything")]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Show the history of changes to installed packages")]
+pub struct History {
+    #[structopt(help = "Only show the history of these packages")]
+    pub packages: Vec<String>,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Show why a package is installed")]
+pub struct Why {
+    #[structopt(help = "Package to explain")]
+    pub package: String,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
+#[derive(Debug, StructOpt)]
+#[structopt(about = "Make the installed packages match a manifest")]
+pub struct Sync {
+    #[structopt(help = "Manifest of desired packages", parse(from_os_str))]
+    pub manifest_path: PathBuf,
+    #[structopt(long, help = "Uninstall packages not listed in the manifest")]
+    pub prune: bool,
+    #[structopt(long, help = "Show what would be done without changing anything")]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    global_opts: super::GlobalOpts,
+}
+
 use crate::{ConfigPath, Platform};
 
 impl ConfigPath for Download {
@@ -141,6 +232,111 @@ impl Platform for Init {
     }
 }
 
+impl ConfigPath for Recover {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Recover {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for Export {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Export {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for Import {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Import {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for History {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for History {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for Why {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Why {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for Upgrade {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Upgrade {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
+impl ConfigPath for Sync {
+    #[inline]
+    fn config_path(&self) -> Option<&Path> {
+        self.global_opts.config_path.as_ref().map(PathBuf::as_path)
+    }
+
+}
+
+impl Platform for Sync {
+    #[inline]
+    fn platform(&self) -> Option<&str> {
+        self.global_opts.platform.as_ref().map(|x| &**x)
+    }
+}
+
 impl ConfigPath for Config {
     #[inline]
     fn config_path(&self) -> Option<&Path> {
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use futures::stream::StreamExt;
 use futures::pin_mut;
 
-use pahkat_client::{PackageKey, PackageStore, DownloadEvent};
+use pahkat_client::{CancellationToken, PackageKey, PackageStore, DownloadEvent};
 
 pub async fn download<'a>(
     store: Arc<dyn PackageStore>,
@@ -23,6 +23,9 @@ pub async fn download<'a>(
         })
         .collect::<Result<Vec<_>, _>>()?;
 
+    let cancel = CancellationToken::new();
+    crate::cancel_on_ctrl_c(cancel.clone());
+
     for key in keys {
         // let pb = indicatif::ProgressBar::new(0);
         // pb.set_style(indicatif::ProgressStyle::default_bar()
@@ -40,7 +43,7 @@ pub async fn download<'a>(
         //     true
         // });
 
-        let mut download = store.download(&key);
+        let mut download = store.download(&key, cancel.clone());
 
         // pin_mut!(download);
 
@@ -50,6 +53,9 @@ pub async fn download<'a>(
                     std::fs::copy(&pkg_path, output_path.join(pkg_path.file_name().unwrap()))?;
                     std::fs::remove_file(&pkg_path)?;
                 }
+                DownloadEvent::Error(e) => {
+                    anyhow::bail!("Could not download {}: {}", &key, e);
+                }
                 _ => {}
             }
         }
@@ -0,0 +1,42 @@
+use pahkat_client::{PackageKey, PackageStore};
+
+use crate::cli::command::History;
+
+pub(crate) fn history(store: &dyn PackageStore, args: &History) -> Result<(), anyhow::Error> {
+    let keys = args
+        .packages
+        .iter()
+        .map(|id| {
+            store
+                .find_package_by_id(id)
+                .map(|x| x.0.without_query_params())
+                .ok_or_else(|| anyhow::anyhow!("Could not find package for: `{}`", id))
+        })
+        .collect::<anyhow::Result<Vec<PackageKey>>>()?;
+
+    let entries = store
+        .history()?
+        .into_iter()
+        .filter(|x| keys.is_empty() || keys.contains(&x.key))
+        .collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        println!("No history recorded.");
+        return Ok(());
+    }
+
+    for entry in entries.iter() {
+        println!(
+            "{} {} {} ({:?}) {} -> {} [{}]",
+            entry.timestamp,
+            entry.event,
+            entry.key,
+            entry.target,
+            entry.from_version.as_ref().map(|x| &**x).unwrap_or("-"),
+            entry.to_version.as_ref().map(|x| &**x).unwrap_or("-"),
+            entry.initiator
+        );
+    }
+
+    Ok(())
+}
@@ -4,7 +4,10 @@ use std::sync::Arc;
 use futures::stream::StreamExt;
 
 use pahkat_client::{
-    transaction::{PackageAction, PackageTransaction},
+    transaction::{
+        history::Initiator, plan::TransactionPlan, PackageAction, PackageActionType,
+        PackageTransaction,
+    },
     package_store::InstallTarget,
     PackageStore,
     PackageKey, DownloadEvent,
@@ -15,18 +18,30 @@ pub(crate) async fn install<'a>(
     store: Arc<dyn PackageStore>,
     packages: &'a Vec<String>,
     target: InstallTarget,
+    dry_run: bool,
     args: &'a crate::Args,
 ) -> Result<(), anyhow::Error> {
     let keys: Vec<PackageKey> = packages
         .iter()
         .map(|id| {
+            // A specific release, which may be older than the installed one, can be requested
+            // with `id@version`. Package keys carry the version as a query parameter instead.
+            let (id, version) = match id.find('@') {
+                Some(i) if !id.contains("://") => (&id[..i], Some(&id[i + 1..])),
+                _ => (&**id, None),
+            };
+
             let mut key: PackageKey = store
                 .find_package_by_id(id)
                 .map(|x| x.0)
                 .ok_or_else(|| anyhow::anyhow!("Could not find package for: `{}`", id))?;
 
+            if let Some(version) = version {
+                key.query.version = Some(version.to_string());
+            }
+
             if let Some(platform) = args.platform() {
-                key.query.platform = Some(platform.to_string());
+                key.query.platform = Some(platform.into());
             }
 
             Ok(key)
@@ -58,21 +73,81 @@ pub(crate) async fn install<'a>(
         keys.iter()
             .map(|x| PackageAction::install(x.clone(), target.clone()))
             .collect(),
-    )?;
+    )?
+    .with_initiator(Initiator::Cli);
+
+    if dry_run {
+        let plan = transaction.plan()?;
+        print_plan(&*store, &plan);
+        plan.validate()?;
+        return Ok(());
+    }
+
+    process(store, transaction).await
+}
+
+pub(crate) fn print_plan(store: &dyn PackageStore, plan: &TransactionPlan) {
+    let languages = crate::locale::languages();
+
+    for item in plan.actions.iter() {
+        let versions = match (&item.from_version, &item.to_version) {
+            (Some(from), Some(to)) => format!("{} -> {}", from, to),
+            (None, Some(to)) => to.to_string(),
+            (Some(from), None) => from.to_string(),
+            (None, None) => "".to_string(),
+        };
+
+        println!(
+            "{:?} {} ({}) {} (download: {} bytes, installed size: {:+} bytes)",
+            item.action.action,
+            crate::locale::display_name(store, &item.action.id, &languages),
+            item.action.id,
+            versions,
+            item.download_size,
+            item.installed_size_delta
+        );
+    }
+
+    println!("Total download size: {} bytes", plan.download_size);
+    println!("Total installed size change: {:+} bytes", plan.installed_size_delta);
+
+    for space in plan.space.iter() {
+        println!(
+            "{}: {} bytes required, {} bytes available",
+            space.path.display(),
+            space.required,
+            space.available
+        );
+    }
+
+    if plan.is_reboot_required {
+        println!("A reboot will be required.");
+    }
+}
+
+pub(crate) async fn process(
+    store: Arc<dyn PackageStore>,
+    transaction: PackageTransaction,
+) -> Result<(), anyhow::Error> {
+    transaction.plan()?.validate()?;
+
+    let cancel = transaction.cancellation_token();
+    crate::cancel_on_ctrl_c(cancel.clone());
 
     for record in transaction.actions().iter() {
-        let id = record.action.id.clone();
-        let mut download = store.download(&record.action.id);
+        if record.action.action != PackageActionType::Install {
+            continue;
+        }
 
-        // TODO: handle cancel here
+        let id = record.action.id.clone();
+        let mut download = store.download(&record.action.id, cancel.clone());
 
         println!("Downloading {}", id);
 
         while let Some(event) = download.next().await {
             match event {
                 DownloadEvent::Error(e) => {
-                    println!("Error: {}", e);
-                    return Ok(());
+                    anyhow::bail!("Could not download {}: {}", id, e);
                 }
                 DownloadEvent::Progress((current, total)) => {
                     println!("Progress: {}/{}", current, total);
@@ -84,14 +159,12 @@ pub(crate) async fn install<'a>(
         }
     }
 
-    let (canceler, mut tx) = transaction.process();
+    let (_cancel, mut tx) = transaction.process();
 
     while let Some(event) = tx.next().await {
         let mut is_completed = false;
         use pahkat_client::transaction::TransactionEvent;
 
-        // TODO: handle cancel here
-
         match event {
             TransactionEvent::Installing(id) => {
                 println!("Installing: {}", id);
@@ -99,12 +172,11 @@ pub(crate) async fn install<'a>(
             TransactionEvent::Uninstalling(id) => {
                 println!("Uninstalling: {}", id);
             }
-            TransactionEvent::Progress(id, msg) => {
-                println!("Progress: {} {}", id, msg);
+            TransactionEvent::Progress(id, (current, total), msg) => {
+                println!("{}: {} {}/{}", msg, id, current, total);
             }
             TransactionEvent::Error(id, err) => {
-                println!("Error: {} {}", id, err);
-                return Ok(());
+                anyhow::bail!("Could not process {}: {}", id, err);
             }
             TransactionEvent::Complete => {
                 println!("Complete!");
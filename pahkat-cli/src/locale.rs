@@ -0,0 +1,51 @@
+use pahkat_client::types::lang_tag::is_valid_lang_tag;
+use pahkat_client::{PackageKey, PackageStore};
+
+/// The user's preferred languages as BCP 47 tags, most preferred first, read from the
+/// POSIX locale environment variables.
+pub(crate) fn languages() -> Vec<String> {
+    let mut languages = vec![];
+
+    // A GNU extension listing several languages in order of preference
+    if let Ok(value) = std::env::var("LANGUAGE") {
+        languages.extend(value.split(':').filter_map(lang_tag));
+    }
+
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty());
+
+    if let Some(tag) = locale.as_deref().and_then(lang_tag) {
+        languages.push(tag);
+    }
+
+    languages
+}
+
+/// Converts a POSIX locale such as `se_NO.UTF-8@euro` to a language tag.
+fn lang_tag(locale: &str) -> Option<String> {
+    let locale = locale.split(|c| c == '.' || c == '@').next()?;
+    if locale == "C" || locale == "POSIX" {
+        return None;
+    }
+
+    let tag = locale.replace('_', "-");
+    if is_valid_lang_tag(&tag) {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+/// The name of the package in the best matching language, or its id.
+pub(crate) fn display_name(
+    store: &dyn PackageStore,
+    key: &PackageKey,
+    languages: &[String],
+) -> String {
+    store
+        .find_package_by_key(key)
+        .and_then(|package| package.name().and_then(|x| x.lookup(languages)).cloned())
+        .unwrap_or_else(|| key.id.clone())
+}
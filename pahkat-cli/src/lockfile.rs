@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use pahkat_client::{
+    lockfile::Lockfile, transaction::history::Initiator, PackageStore, PackageTransaction,
+};
+
+use crate::cli::command::{Export, Import};
+
+pub(crate) fn export(store: &dyn PackageStore, args: &Export) -> Result<(), anyhow::Error> {
+    let lockfile = store.lockfile()?;
+
+    match args.output_path.as_ref() {
+        Some(path) => {
+            lockfile.save(path)?;
+            println!(
+                "Exported {} packages to {}",
+                lockfile.packages.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", lockfile.to_string()?),
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn import(store: Arc<dyn PackageStore>, args: &Import) -> Result<(), anyhow::Error> {
+    let lockfile = Lockfile::load(&args.lockfile_path)?;
+    let transaction = PackageTransaction::from_lockfile(Arc::clone(&store), &lockfile)?
+        .with_initiator(Initiator::Cli);
+
+    if args.dry_run {
+        let plan = transaction.plan()?;
+        crate::install::print_plan(&*store, &plan);
+        plan.validate()?;
+        return Ok(());
+    }
+
+    crate::install::process(store, transaction).await
+}
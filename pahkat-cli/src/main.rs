@@ -1,8 +1,15 @@
 mod cli;
 mod download;
+mod history;
 mod install;
+mod locale;
+mod lockfile;
+mod recover;
 mod status;
+mod sync;
 mod uninstall;
+mod upgrade;
+mod why;
 mod config;
 
 use anyhow::{Context, Result};
@@ -20,7 +27,7 @@ fn config_path(holder: &dyn ConfigPath) -> Result<PathBuf> {
         .with_context(|| "No default config path could be found")
 }
 
-use pahkat_client::{Config, PackageStore};
+use pahkat_client::{CancellationToken, Config, PackageStore};
 use std::sync::Arc;
 
 // #[inline(always)]
@@ -44,6 +51,14 @@ async fn store(config_path: Option<&Path>) -> anyhow::Result<Arc<dyn PackageStor
         println!("WARNING: There are no repositories in the given config.");
     }
 
+    if let Ok(Some(_)) = store.interrupted_transaction() {
+        println!("WARNING: An interrupted transaction was found. Run `pahkat recover` to resume or roll it back.");
+    }
+
+    for m in store.moves().read().unwrap().iter() {
+        println!("NOTE: The repository {} has moved to {}.", m.from, m.to);
+    }
+
     Ok(store)
 }#
 
@@ -75,9 +90,28 @@ async fn store(config_path: Option<&Path>) -> anyhow::Result<Arc<dyn PackageStor
         println!("WARNING: There are no repositories in the given config.");
     }
 
+    if let Ok(Some(_)) = store.interrupted_transaction() {
+        println!("WARNING: An interrupted transaction was found. Run `pahkat recover` to resume or roll it back.");
+    }
+
+    for m in store.moves().read().unwrap().iter() {
+        println!("NOTE: The repository {} has moved to {}.", m.from, m.to);
+    }
+
     Ok(store)
 }
 
+/// Cancels the given token on Ctrl-C, letting in-flight work stop cleanly instead of
+/// killing the process outright.
+fn cancel_on_ctrl_c(cancel: CancellationToken) {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            println!("Cancelling…");
+            cancel.cancel();
+        }
+    });
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::init();
@@ -112,12 +146,40 @@ async fn main() -> anyhow::Result<()> {
         }
         cli::Args::Install(a) => {
             let store = store(args.config_path()).await?;
-            install::install(store, &a.packages, Default::default(), &args).await?
+            install::install(store, &a.packages, Default::default(), a.dry_run, &args).await?
+        }
+        cli::Args::Upgrade(a) => {
+            let store = store(args.config_path()).await?;
+            upgrade::upgrade(store, a).await?
         }
         cli::Args::Config(a) => {
             let store = store(args.config_path()).await?;
             config::config(store, a, Default::default(), &args).await?
         }
+        cli::Args::Recover(a) => {
+            let store = store(args.config_path()).await?;
+            recover::recover(store, a).await?
+        }
+        cli::Args::Export(a) => {
+            let store = store(args.config_path()).await?;
+            lockfile::export(&*store, a)?
+        }
+        cli::Args::Import(a) => {
+            let store = store(args.config_path()).await?;
+            lockfile::import(store, a).await?
+        }
+        cli::Args::History(a) => {
+            let store = store(args.config_path()).await?;
+            history::history(&*store, a)?
+        }
+        cli::Args::Why(a) => {
+            let store = store(args.config_path()).await?;
+            why::why(&*store, a, Default::default())?
+        }
+        cli::Args::Sync(a) => {
+            let store = store(args.config_path()).await?;
+            sync::sync(store, a).await?
+        }
     }
 
     Ok(())
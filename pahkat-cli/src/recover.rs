@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use pahkat_client::{
+    transaction::{history::Initiator, journal::TransactionJournal, PackageTransaction},
+    PackageStore,
+};
+
+use crate::cli::command::Recover;
+
+pub(crate) async fn recover(store: Arc<dyn PackageStore>, args: &Recover) -> Result<(), anyhow::Error> {
+    let journal = match store.interrupted_transaction()? {
+        Some(v) => v,
+        None => {
+            println!("No interrupted transaction found.");
+            return Ok(());
+        }
+    };
+
+    if args.discard {
+        TransactionJournal::discard(&*store.config().read().unwrap())?;
+        println!("Discarded interrupted transaction.");
+        return Ok(());
+    }
+
+    let transaction = if args.resume {
+        PackageTransaction::resume(Arc::clone(&store))?
+    } else if args.rollback {
+        PackageTransaction::rollback(Arc::clone(&store))?
+    } else {
+        println!("Interrupted transaction started on {}:", &journal.started_on);
+        for entry in journal.entries.iter() {
+            println!(
+                "  {:?} {} ({:?})",
+                entry.action.action, entry.action.id, entry.state
+            );
+        }
+        println!("Run again with --resume, --rollback or --discard.");
+        return Ok(());
+    };
+
+    crate::install::process(store, transaction.with_initiator(Initiator::Cli)).await
+}
@@ -12,6 +12,8 @@ pub fn status(
         return Ok(());
     }
 
+    let languages = crate::locale::languages();
+
     for id in packages {
         let (package_key, _) = match store.find_package_by_id(id) {
             Some(v) => v,
@@ -20,9 +22,10 @@ pub fn status(
                 continue;
             }
         };
+        let name = crate::locale::display_name(store, &package_key, &languages);
         match store.status(&package_key, target) {
-            Ok(x) => println!("{}: {:?}", &package_key, x),
-            Err(x) => println!("{}: {:?}", &package_key, x),
+            Ok(x) => println!("{} ({}): {:?}", name, &package_key, x),
+            Err(x) => println!("{} ({}): {:?}", name, &package_key, x),
         }
     }
 
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use pahkat_client::manifest::{Drift, Manifest};
+use pahkat_client::{transaction::history::Initiator, PackageStore, PackageTransaction};
+
+use crate::cli::command::Sync;
+
+fn print_drift(drift: &[Drift]) {
+    for item in drift.iter() {
+        println!(
+            "  {} ({:?}): {} -> {}",
+            &item.key,
+            item.target,
+            item.installed
+                .as_ref()
+                .map(|x| &**x)
+                .unwrap_or("not installed"),
+            item.wanted
+                .as_ref()
+                .map(|x| &**x)
+                .unwrap_or("not installed")
+        );
+    }
+}
+
+pub(crate) async fn sync(store: Arc<dyn PackageStore>, args: &Sync) -> Result<(), anyhow::Error> {
+    let mut manifest = Manifest::load(&args.manifest_path)?;
+    manifest.prune |= args.prune;
+
+    let drift = manifest.drift(&*store)?;
+    if drift.is_empty() {
+        println!("Installed packages already match the manifest.");
+        return Ok(());
+    }
+
+    let transaction = PackageTransaction::from_manifest(Arc::clone(&store), &manifest)?
+        .with_initiator(Initiator::Cli);
+
+    if args.dry_run {
+        println!("Packages that differ from the manifest:");
+        print_drift(&drift);
+        let plan = transaction.plan()?;
+        crate::install::print_plan(&*store, &plan);
+        plan.validate()?;
+        return Ok(());
+    }
+
+    crate::install::process(Arc::clone(&store), transaction).await?;
+
+    let drift = manifest.drift(&*store)?;
+    if !drift.is_empty() {
+        println!("Packages that still differ from the manifest:");
+        print_drift(&drift);
+        anyhow::bail!("{} packages could not be synchronised", drift.len());
+    }
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use std::path::Path;
 
-use pahkat_client::{package_store::InstallTarget, PackageStore};
+use pahkat_client::{
+    package_store::InstallTarget,
+    transaction::history::{HistoryEntry, Initiator},
+    PackageStore,
+};
 
 pub fn uninstall(
     store: &dyn PackageStore,
@@ -13,8 +17,14 @@ pub fn uninstall(
             .map(|x| x.0)
             .ok_or_else(|| anyhow::anyhow!("Could not find package for: `{}`", id))?;
         println!("Uninstalling {}", &pkg_key);
+        let from_version = store.installed_version(&pkg_key, target).ok().flatten();
         let status = store.uninstall(&pkg_key, target)?;
         println!("{:?}", status);
+
+        let entry = HistoryEntry::new(&pkg_key, target, from_version, None, Initiator::Cli);
+        if let Err(e) = store.record_history(&entry) {
+            eprintln!("Could not record package history: {}", e);
+        }
     }
     Ok(())
 }
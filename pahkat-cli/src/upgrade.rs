@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use pahkat_client::{
+    transaction::history::Initiator, PackageAction, PackageStore, PackageTransaction,
+};
+
+use crate::cli::command::Upgrade;
+
+pub(crate) async fn upgrade(
+    store: Arc<dyn PackageStore>,
+    args: &Upgrade,
+) -> Result<(), anyhow::Error> {
+    let updates = store.updates()?;
+
+    if updates.is_empty() {
+        println!("All installed packages are up to date.");
+        return Ok(());
+    }
+
+    let languages = crate::locale::languages();
+
+    println!("Available updates:");
+    for update in updates.iter() {
+        println!(
+            "  {} ({}, {:?}): {} -> {}{} (download: {} bytes{})",
+            crate::locale::display_name(&*store, &update.key, &languages),
+            &update.key,
+            update.target,
+            &update.installed_version,
+            &update.candidate_version,
+            update
+                .channel
+                .as_ref()
+                .map(|x| format!(" [{}]", x))
+                .unwrap_or_default(),
+            update.download_size,
+            if update.is_reboot_required {
+                ", requires reboot"
+            } else {
+                ""
+            }
+        );
+    }
+
+    let transaction = PackageTransaction::new(
+        Arc::clone(&store),
+        updates
+            .into_iter()
+            .map(|x| PackageAction::install(x.key, x.target))
+            .collect(),
+    )?
+    .with_initiator(Initiator::Cli);
+
+    if args.dry_run {
+        let plan = transaction.plan()?;
+        crate::install::print_plan(&*store, &plan);
+        plan.validate()?;
+        return Ok(());
+    }
+
+    crate::install::process(store, transaction).await
+}
@@ -0,0 +1,41 @@
+use pahkat_client::{
+    package_store::{InstallReason, InstallTarget},
+    PackageStore,
+};
+
+use crate::cli::command::Why;
+
+pub(crate) fn why(
+    store: &dyn PackageStore,
+    args: &Why,
+    target: InstallTarget,
+) -> Result<(), anyhow::Error> {
+    let key = store
+        .find_package_by_id(&args.package)
+        .map(|x| x.0)
+        .ok_or_else(|| anyhow::anyhow!("Could not find package for: `{}`", &args.package))?;
+
+    if store.installed_version(&key, target)?.is_none() {
+        println!("{} is not installed.", &key);
+        return Ok(());
+    }
+
+    let result = store.reverse_dependencies(&key, target)?;
+
+    match result.reason {
+        InstallReason::Requested => println!("{} was installed explicitly.", &result.key),
+        InstallReason::Dependency => println!("{} was installed as a dependency.", &result.key),
+        InstallReason::Unknown => {}
+    }
+
+    if result.dependents.is_empty() {
+        println!("No installed packages depend on {}.", &result.key);
+    } else {
+        println!("Required by:");
+        for dependent in result.dependents.iter() {
+            println!("  {}", dependent);
+        }
+    }
+
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 use crate::transaction::{PackageStatus, PackageStatusError};
 
 use pahkat_types::package::Version;
@@ -6,14 +8,22 @@ pub(crate) fn cmp(
     installed_version: &str,
     candidate_version: &Version,
 ) -> Result<PackageStatus, PackageStatusError> {
-    let installed_version = match Version::new(installed_version) {
-        Ok(v) => v,
-        Err(_) => return Err(PackageStatusError::ParsingVersion),
-    };
+    // The installed version was most likely written in the candidate's scheme, and versions
+    // of different schemes cannot be ordered
+    let installed_version =
+        Version::from_version_type(candidate_version.version_type(), installed_version)
+            .or_else(|_| Version::new(installed_version))
+            .map_err(|_| PackageStatusError::ParsingVersion)?;
 
-    if candidate_version > &installed_version {
-        Ok(PackageStatus::RequiresUpdate)
-    } else {
-        Ok(PackageStatus::UpToDate)
+    match candidate_version.partial_cmp(&installed_version) {
+        Some(Ordering::Greater) => Ok(PackageStatus::RequiresUpdate),
+        Some(Ordering::Less) => Ok(PackageStatus::NewerThanAvailable),
+        Some(Ordering::Equal) => Ok(PackageStatus::UpToDate),
+        // Versions that cannot be ordered, such as opaque ones, only tell whether the
+        // installed version is the one in the repository.
+        None if candidate_version.to_string() == installed_version.to_string() => {
+            Ok(PackageStatus::UpToDate)
+        }
+        None => Ok(PackageStatus::RequiresUpdate),
     }
 }
@@ -152,6 +152,29 @@ impl Repos {
         Ok(result)
     }
 
+    /// Replaces the repository at `from` with `to`, keeping its record and its position.
+    pub fn replace(&mut self, from: &RepoUrl, to: RepoUrl) -> Result<bool, FileError> {
+        if !self.data.0.contains_key(from) {
+            return Ok(false);
+        }
+
+        if self.data.0.contains_key(&to) {
+            self.data.0.shift_remove(from);
+        } else {
+            let data = std::mem::take(&mut self.data.0);
+            self.data.0 = data
+                .into_iter()
+                .map(|(k, v)| if &k == from { (to.clone(), v) } else { (k, v) })
+                .collect();
+        }
+
+        if self.permission == Permission::ReadWrite {
+            self.data.save(&self.path)?;
+        }
+
+        Ok(true)
+    }
+
     pub fn data(&self) -> &ReposData {
         &self.data
     }
@@ -17,6 +17,9 @@ pub struct SettingsData {
     pub tmp_dir: ConfigPath,
     #[serde(default)]
     pub max_concurrent_downloads: u8,
+    /// Whether repositories that moved are replaced by their new URL in `repos.toml`.
+    #[serde(default = "defaults::rewrite_moved_repos")]
+    pub rewrite_moved_repos: bool,
 }
 
 impl Default for SettingsData {
@@ -25,6 +28,7 @@ impl Default for SettingsData {
             cache_dir: defaults::cache_dir(),
             tmp_dir: defaults::tmp_dir(),
             max_concurrent_downloads: 0,
+            rewrite_moved_repos: defaults::rewrite_moved_repos(),
         }
     }
 }
@@ -164,4 +168,8 @@ impl Settings {
     pub fn max_concurrent_downloads(&self) -> u8 {
         self.data.max_concurrent_downloads
     }
+
+    pub fn rewrite_moved_repos(&self) -> bool {
+        self.data.rewrite_moved_repos
+    }
 }
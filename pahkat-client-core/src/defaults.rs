@@ -1,4 +1,5 @@
 use crate::config::ConfigPath;
+use once_cell::sync::Lazy;
 use std::path::{Path, PathBuf};
 use url::Url;
 
@@ -111,6 +112,11 @@ pub fn uninstall_path() -> PathBuf {
     pathos::user::app_data_dir(APP_PATH).join("uninstall")
 }
 
+#[inline(always)]
+pub(crate) fn rewrite_moved_repos() -> bool {
+    true
+}
+
 macro_rules! platform {
     ($name:expr) => {{
         #[cfg(target_os = $name)]
@@ -152,6 +158,65 @@ pub(crate) const fn arch() -> Option<&'static str> {
     arch!("powerpc64");
 }
 
+/// Set to use an OS version other than the one detected, such as when testing which targets
+/// are available on other versions.
+const OS_VERSION_VAR: &str = "PAHKAT_OS_VERSION";
+
+static OS_VERSION: Lazy<Option<String>> = Lazy::new(|| {
+    std::env::var(OS_VERSION_VAR)
+        .ok()
+        .filter(|x| !x.is_empty())
+        .or_else(detect_os_version)
+});
+
+/// The version of the running OS, if it could be detected.
+pub(crate) fn os_version() -> Option<&'static str> {
+    OS_VERSION.as_deref()
+}
+
+#[cfg(windows)]
+fn detect_os_version() -> Option<String> {
+    use winreg::enums::HKEY_LOCAL_MACHINE;
+    use winreg::RegKey;
+
+    let key = RegKey::predef(HKEY_LOCAL_MACHINE)
+        .open_subkey(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion")
+        .ok()?;
+    let build: String = key.get_value("CurrentBuildNumber").ok()?;
+
+    // Windows 10 no longer updates `CurrentVersion`, which is stuck at 6.3
+    let major: Result<u32, _> = key.get_value("CurrentMajorVersionNumber");
+    let minor: Result<u32, _> = key.get_value("CurrentMinorVersionNumber");
+    match (major, minor) {
+        (Ok(major), Ok(minor)) => Some(format!("{}.{}.{}", major, minor, build)),
+        _ => {
+            let version: String = key.get_value("CurrentVersion").ok()?;
+            Some(format!("{}.{}", version, build))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect_os_version() -> Option<String> {
+    let info = plist::Value::from_file("/System/Library/CoreServices/SystemVersion.plist").ok()?;
+    let version = info.as_dictionary()?.get("ProductVersion")?.as_string()?;
+    Some(version.to_string())
+}
+
+/// The `VERSION_ID` of the distribution, such as `22.04`, rather than the kernel version.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn detect_os_version() -> Option<String> {
+    let release = std::fs::read_to_string("/etc/os-release")
+        .or_else(|_| std::fs::read_to_string("/usr/lib/os-release"))
+        .ok()?;
+
+    release.lines().find_map(|line| {
+        let value = line.strip_prefix("VERSION_ID=")?.trim();
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        Some(value.to_string()).filter(|x| !x.is_empty())
+    })
+}
+
 #[inline(always)]
 pub(crate) fn payloads() -> &'static [&'static str] {
     #[cfg(all(feature = "windows", not(feature = "macos"), not(feature = "prefix")))]
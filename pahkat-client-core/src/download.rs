@@ -8,6 +8,7 @@ use url::Url;
 
 use crate::ext::PathExt;
 use crate::package_store::DownloadEvent;
+use crate::CancellationToken;
 
 pub trait Download {
     fn download<F>(
@@ -20,6 +21,9 @@ pub trait Download {
         F: Fn(u64, u64) -> bool + Send + 'static;
 }
 
+/// How often a download waiting for data checks whether it was cancelled.
+const CANCEL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(250);
+
 pub(crate) struct DownloadManager {
     client: reqwest::Client,
     path: PathBuf,
@@ -53,6 +57,7 @@ impl DownloadManager {
         &self,
         url: &Url,
         dest_path: P,
+        cancel: CancellationToken,
     ) -> Result<
         std::pin::Pin<
             Box<dyn futures::stream::Stream<Item = DownloadEvent> + Send + Sync + 'static>,
@@ -169,7 +174,28 @@ impl DownloadManager {
         let stream = async_stream::stream! {
             let mut file = BufWriter::new(file);
             loop {
-                let chunk = res.chunk().await.map_err(DownloadError::ReqwestError);
+                // Wait for the next chunk in short slices, so a stalled connection cannot
+                // keep a cancelled download waiting.
+                let chunk = loop {
+                    if cancel.is_cancelled() {
+                        break None;
+                    }
+
+                    if let Ok(v) = tokio::time::timeout(CANCEL_POLL_INTERVAL, res.chunk()).await {
+                        break Some(v);
+                    }
+                };
+
+                // The partial download is kept so that it can be resumed later.
+                let chunk = match chunk {
+                    Some(v) => v.map_err(DownloadError::ReqwestError),
+                    None => {
+                        log::debug!("Download of {:?} cancelled", &tmp_dest_path);
+                        let _ = file.flush();
+                        yield DownloadEvent::Error(DownloadError::UserCancelled);
+                        return;
+                    }
+                };
                 match chunk {
                     Ok(v) => match v {
                         None => {
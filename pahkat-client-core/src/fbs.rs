@@ -18,6 +18,8 @@ pub(crate) trait TargetExt {
 
 pub(crate) trait PackagesExt<B: AsRef<[u8]>> {
     fn packages(&self) -> Option<Map<'_, &'_ str, pahkat_fbs::Descriptor<&'_ [u8]>>>;
+    fn synthetics(&self) -> Option<Map<'_, &'_ str, pahkat_fbs::Synthetic<&'_ [u8]>>>;
+    fn redirects(&self) -> Option<Map<'_, &'_ str, pahkat_fbs::Redirect<&'_ [u8]>>>;
 }
 
 impl PackagesExt<&'_ [u8]> for pahkat_fbs::Packages<&'_ [u8]> {
@@ -26,6 +28,18 @@ impl PackagesExt<&'_ [u8]> for pahkat_fbs::Packages<&'_ [u8]> {
         let values = self.packages_values().ok()??;
         Some(Map::new(keys, values))
     }
+
+    fn synthetics(&self) -> Option<Map<'_, &'_ str, pahkat_fbs::Synthetic<&'_ [u8]>>> {
+        let keys = self.synthetics_keys().ok()??;
+        let values = self.synthetics_values().ok()??;
+        Some(Map::new(keys, values))
+    }
+
+    fn redirects(&self) -> Option<Map<'_, &'_ str, pahkat_fbs::Redirect<&'_ [u8]>>> {
+        let keys = self.redirects_keys().ok()??;
+        let values = self.redirects_values().ok()??;
+        Some(Map::new(keys, values))
+    }
 }
 
 impl<B: AsRef<[u8]>> DescriptorExt for pahkat_fbs::Descriptor<B> {
@@ -50,11 +64,35 @@ impl<B: AsRef<[u8]>> TargetExt for pahkat_fbs::Target<B> {
     }
 }
 
+impl<B: AsRef<[u8]>> DescriptorExt for pahkat_fbs::Synthetic<B> {
+    fn name(&self) -> Option<Map<'_, &'_ str, &'_ str>> {
+        let keys = self.name_keys().ok()??;
+        let values = self.name_values().ok()??;
+        Some(Map::new(keys, values))
+    }
+
+    fn description(&self) -> Option<Map<'_, &'_ str, &'_ str>> {
+        let keys = self.description_keys().ok()??;
+        let values = self.description_values().ok()??;
+        Some(Map::new(keys, values))
+    }
+}
+
+impl<B: AsRef<[u8]>> TargetExt for pahkat_fbs::SyntheticTarget<B> {
+    fn dependencies(&self) -> Option<Map<'_, &'_ str, &'_ str>> {
+        let keys = self.dependencies_keys().ok()??;
+        let values = self.dependencies_values().ok()??;
+        Some(Map::new(keys, values))
+    }
+}
+
 fn build_target<B: AsRef<[u8]>>(
     t: &pahkat_fbs::Target<B>,
 ) -> Result<pahkat_types::payload::Target, butte::Error> {
-    let platform = t.platform()?.to_string();
-    let arch = t.arch()?.map(str::to_string);
+    let platform = pahkat_types::Platform::from(t.platform()?);
+    let arch = t.arch()?.map(pahkat_types::Arch::from);
+    let min_os_version = t.min_os_version()?.map(str::to_string);
+    let max_os_version = t.max_os_version()?.map(str::to_string);
     let dependencies = t
         .dependencies()
         .map(|x| {
@@ -65,6 +103,18 @@ fn build_target<B: AsRef<[u8]>>(
             out
         })
         .unwrap_or_else(|| Default::default());
+    let conflicts = t
+        .conflicts()?
+        .map(|x| x.iter().map(|x| x.unwrap_or("").to_string()).collect())
+        .unwrap_or(vec![]);
+    let provides = t
+        .provides()?
+        .map(|x| x.iter().map(|x| x.unwrap_or("").to_string()).collect())
+        .unwrap_or(vec![]);
+    let replaces = t
+        .replaces()?
+        .map(|x| x.iter().map(|x| x.unwrap_or("").to_string()).collect())
+        .unwrap_or(vec![]);
     let payload = match t.payload()? {
         pahkat_fbs::Payload::WindowsExecutable(x) => {
             pahkat_types::payload::Payload::WindowsExecutable(
@@ -97,6 +147,16 @@ fn build_target<B: AsRef<[u8]>>(
                 .url(x.url()?.parse::<url::Url>().unwrap())
                 .size(x.size()?.unwrap())
                 .installed_size(x.installed_size()?.unwrap())
+                .pre_install(x.pre_install()?.map(str::to_string))
+                .post_install(x.post_install()?.map(str::to_string))
+                .pre_uninstall(x.pre_uninstall()?.map(str::to_string))
+                .post_uninstall(x.post_uninstall()?.map(str::to_string))
+                .hook_timeout(x.hook_timeout()?.filter(|x| *x > 0))
+                .executables(
+                    x.executables()?
+                        .map(|x| x.iter().map(|x| x.unwrap_or("").to_string()).collect())
+                        .unwrap_or(vec![]),
+                )
                 .build(),
         ),
     };
@@ -104,7 +164,12 @@ fn build_target<B: AsRef<[u8]>>(
     Ok(pahkat_types::payload::Target::builder()
         .platform(platform)
         .arch(arch)
+        .min_os_version(min_os_version)
+        .max_os_version(max_os_version)
         .dependencies(dependencies)
+        .conflicts(conflicts)
+        .provides(provides)
+        .replaces(replaces)
         .payload(payload)
         .build())
 }
@@ -113,8 +178,6 @@ impl<'a> TryFrom<&'a pahkat_fbs::Descriptor<&'a [u8]>> for pahkat_types::package
     type Error = butte::Error;
 
     fn try_from(pkg: &'a pahkat_fbs::Descriptor<&'a [u8]>) -> Result<Self, Self::Error> {
-        use std::collections::BTreeMap;
-
         let descriptor = pahkat_types::package::Descriptor::builder()
             .package(
                 pahkat_types::package::DescriptorData::builder()
@@ -128,24 +191,12 @@ impl<'a> TryFrom<&'a pahkat_fbs::Descriptor<&'a [u8]>> for pahkat_types::package
             )
             .name(
                 pkg.name()
-                    .map(|x| {
-                        let mut out = BTreeMap::new();
-                        for (k, v) in x.iter() {
-                            out.insert(k.to_string(), v.to_string());
-                        }
-                        out
-                    })
+                    .map(|x| lang_tag_map(&x))
                     .unwrap_or_else(|| Default::default()),
             )
             .description(
                 pkg.description()
-                    .map(|x| {
-                        let mut out = BTreeMap::new();
-                        for (k, v) in x.iter() {
-                            out.insert(k.to_string(), v.to_string());
-                        }
-                        out
-                    })
+                    .map(|x| lang_tag_map(&x))
                     .unwrap_or_else(|| Default::default()),
             )
             .release(
@@ -154,10 +205,22 @@ impl<'a> TryFrom<&'a pahkat_fbs::Descriptor<&'a [u8]>> for pahkat_types::package
                     .iter()
                     .filter_map(Result::ok)
                     .map(|x| {
+                        // A version that does not parse in its declared scheme leaves the
+                        // release unusable, but not the rest of the package
+                        let version = pahkat_types::package::version::Version::from_version_type(
+                            x.version_type()?.unwrap_or(0),
+                            x.version()?,
+                        );
+                        let version = match version {
+                            Ok(v) => v,
+                            Err(e) => {
+                                log::warn!("Skipping release of {}: {}", pkg.id()?, e);
+                                return Ok(None);
+                            }
+                        };
+
                         let release = pahkat_types::package::Release::builder()
-                            .version(
-                                pahkat_types::package::version::Version::new(x.version()?).unwrap(),
-                            )
+                            .version(version)
                             .channel(x.channel()?.map(|x| x.to_string()))
                             .target(
                                 x.target()?
@@ -168,8 +231,9 @@ impl<'a> TryFrom<&'a pahkat_fbs::Descriptor<&'a [u8]>> for pahkat_types::package
                                     .collect::<Result<Vec<_>, _>>()?,
                             )
                             .build();
-                        Ok(release)
+                        Ok(Some(release))
                     })
+                    .filter_map(Result::transpose)
                     .collect::<Result<Vec<_>, _>>()?,
             )
             .build();
@@ -178,6 +242,144 @@ impl<'a> TryFrom<&'a pahkat_fbs::Descriptor<&'a [u8]>> for pahkat_types::package
     }
 }
 
+fn build_verifier<B: AsRef<[u8]>>(
+    t: &pahkat_fbs::SyntheticTarget<B>,
+) -> Result<pahkat_types::synth::Verifier, butte::Error> {
+    use pahkat_types::synth::{file, macos, windows, Verifier};
+
+    let verifier = match t.verifier()? {
+        pahkat_fbs::Verifier::WindowsRegistryKeyRef(x) => Verifier::WindowsRegistryKey(
+            windows::RegistryKey::builder()
+                .path(x.path()?.to_string())
+                .name(x.name()?.to_string())
+                .build(),
+        ),
+        pahkat_fbs::Verifier::MacOSPackageRef(x) => Verifier::MacOSPackageRef(
+            macos::PackageRef::builder()
+                .pkg_id(x.pkg_id()?.to_string())
+                .min_version(x.min_version()?.map(str::to_string))
+                .max_version(x.max_version()?.map(str::to_string))
+                .min_build(x.min_build()?.map(str::to_string))
+                .max_build(x.max_build()?.map(str::to_string))
+                .build(),
+        ),
+        pahkat_fbs::Verifier::MacOSPathRef(x) => Verifier::MacOSPathRef(
+            macos::PathRef::builder()
+                .app_paths(
+                    x.app_paths()?
+                        .map(|x| x.iter().map(|x| x.unwrap_or("").to_string()).collect())
+                        .unwrap_or(vec![]),
+                )
+                .min_version(x.min_version()?.map(str::to_string))
+                .max_version(x.max_version()?.map(str::to_string))
+                .min_build(x.min_build()?.map(str::to_string))
+                .max_build(x.max_build()?.map(str::to_string))
+                .build(),
+        ),
+        pahkat_fbs::Verifier::FileRef(x) => Verifier::FileRef(
+            file::FileRef::builder()
+                .path(x.path()?.to_string())
+                .version_file(x.version_file()?.map(str::to_string))
+                .build(),
+        ),
+    };
+
+    Ok(verifier)
+}
+
+fn build_synthetic_target<B: AsRef<[u8]>>(
+    t: &pahkat_fbs::SyntheticTarget<B>,
+) -> Result<pahkat_types::synth::Target, butte::Error> {
+    let dependencies = t
+        .dependencies()
+        .map(|x| {
+            let mut out = std::collections::BTreeMap::new();
+            for (k, v) in x.iter() {
+                out.insert(k.to_string(), v.to_string());
+            }
+            out
+        })
+        .unwrap_or_else(|| Default::default());
+
+    Ok(pahkat_types::synth::Target::builder()
+        .platform(pahkat_types::Platform::from(t.platform()?))
+        .arch(t.arch()?.map(pahkat_types::Arch::from))
+        .dependencies(dependencies)
+        .verifier(build_verifier(t)?)
+        .build())
+}
+
+impl<'a> TryFrom<&'a pahkat_fbs::Synthetic<&'a [u8]>> for pahkat_types::synth::Descriptor {
+    type Error = butte::Error;
+
+    fn try_from(pkg: &'a pahkat_fbs::Synthetic<&'a [u8]>) -> Result<Self, Self::Error> {
+        let descriptor = pahkat_types::synth::Descriptor::builder()
+            .synthetic(
+                pahkat_types::synth::SyntheticData::builder()
+                    .id(pkg.id()?.into())
+                    .tags(
+                        pkg.tags()?
+                            .map(|tags| tags.iter().map(|x| x.unwrap_or("").to_string()).collect())
+                            .unwrap_or(vec![]),
+                    )
+                    .build(),
+            )
+            .name(
+                pkg.name()
+                    .map(|x| lang_tag_map(&x))
+                    .unwrap_or_else(|| Default::default()),
+            )
+            .description(
+                pkg.description()
+                    .map(|x| lang_tag_map(&x))
+                    .unwrap_or_else(|| Default::default()),
+            )
+            .releases(
+                pkg.release()?
+                    .map(|x| {
+                        x.iter()
+                            .filter_map(Result::ok)
+                            .map(|x| {
+                                let release = pahkat_types::synth::Release::builder()
+                                    .version(x.version()?.to_string())
+                                    .channel(x.channel()?.unwrap_or("").to_string())
+                                    .targets(
+                                        x.target()?
+                                            .map(|t| {
+                                                t.iter()
+                                                    .filter_map(Result::ok)
+                                                    .map(|t| build_synthetic_target(&t))
+                                                    .collect::<Result<Vec<_>, butte::Error>>()
+                                            })
+                                            .transpose()?
+                                            .unwrap_or(vec![]),
+                                    )
+                                    .build();
+                                Ok(release)
+                            })
+                            .collect::<Result<Vec<_>, butte::Error>>()
+                    })
+                    .transpose()?
+                    .unwrap_or(vec![]),
+            )
+            .build();
+
+        Ok(descriptor)
+    }
+}
+
+/// Entries with invalid language tags are skipped, so one bad translation does not hide
+/// the whole package.
+fn lang_tag_map(map: &Map<'_, &'_ str, &'_ str>) -> pahkat_types::LangTagMap<String> {
+    let mut out = pahkat_types::LangTagMap::new();
+    for (k, v) in map.iter() {
+        if let Err(e) = out.insert(k.to_string(), v.to_string()) {
+            log::warn!("{}", e);
+        }
+    }
+    out
+}
+
 pub struct Map<'a, K, V> {
     keys: butte::Vector<'a, butte::ForwardsUOffset<K>>,
     values: butte::Vector<'a, butte::ForwardsUOffset<V>>,
@@ -15,7 +15,7 @@ use crate::package_store::PackageStore;
 use crate::transaction::{
     PackageAction, PackageStatus, PackageStatusError, PackageTransaction, PackageTransactionError,
 };
-use crate::{Config, PackageKey, PrefixPackageStore};
+use crate::{CancellationToken, Config, PackageKey, PrefixPackageStore};
 
 use super::{JsonMarshaler, PackageKeyMarshaler};
 
@@ -94,7 +94,8 @@ pub extern "C" fn pahkat_prefix_package_store_download(
     progress: extern "C" fn(*const libc::c_char, u64, u64) -> bool,
 ) -> Result<PathBuf, Box<dyn Error>> {
     let package_key_str = CString::new(package_key.to_string()).unwrap();
-    let mut stream = handle.download(&package_key);
+    let cancel = CancellationToken::new();
+    let mut stream = handle.download(&package_key, cancel.clone());
 
     let mut path: Option<PathBuf> = None;
 
@@ -106,7 +107,9 @@ pub extern "C" fn pahkat_prefix_package_store_download(
                 return Err(e).box_err();
             }
             DownloadEvent::Progress((current, total)) => {
-                progress(package_key_str.as_ptr(), current, total);
+                if !progress(package_key_str.as_ptr(), current, total) {
+                    cancel.cancel();
+                }
             }
             DownloadEvent::Complete(path_buf) => {
                 path = Some(path_buf);
@@ -205,7 +208,7 @@ pub extern "C" fn pahkat_prefix_transaction_new(
     let actions: Vec<PackageAction> = serde_json::from_str(actions)?;
     PackageTransaction::new(handle as _, actions.clone())
         .map(|x| Box::new(x))
-        .map_err(|e| e.into())
+        .map_err(|e| PackageTransactionError::from(e).into())
 }
 
 #[cthulhu::invoke(return_marshaler = "JsonMarshaler")]
@@ -230,27 +233,27 @@ pub extern "C" fn pahkat_prefix_transaction_process(
             TransactionEvent::Installing(key) => {
                 let k = PackageKeyMarshaler::to_foreign(&key).unwrap();
                 if progress_callback(tag, k, 1) == 0 {
-                    drop(canceler);
+                    canceler.cancel();
                     break;
                 }
             }
             TransactionEvent::Uninstalling(key) => {
                 let k = PackageKeyMarshaler::to_foreign(&key).unwrap();
                 if progress_callback(tag, k, 2) == 0 {
-                    drop(canceler);
+                    canceler.cancel();
                     break;
                 }
             }
             TransactionEvent::Complete => {
                 if progress_callback(tag, Default::default(), 3) == 0 {
-                    drop(canceler);
+                    canceler.cancel();
                     break;
                 }
             }
             TransactionEvent::Error(key, _) => {
                 let k = PackageKeyMarshaler::to_foreign(&key).unwrap();
                 if progress_callback(tag, k, 4) == 0 {
-                    drop(canceler);
+                    canceler.cancel();
                     break;
                 }
             }
@@ -9,15 +9,20 @@ pub mod ffi;
 
 pub mod config;
 pub mod defaults;
+pub mod lockfile;
+pub mod manifest;
 pub mod package_store;
 pub mod repo;
 pub mod transaction;
 
+mod cancel;
 mod cmp;
 mod download;
 mod ext;
 mod fbs;
+mod synth;
 
+pub use self::cancel::CancellationToken;
 pub use self::config::{Config, Permission};
 pub use self::download::Download;
 pub use self::package_store::{DownloadEvent, InstallTarget, PackageStore};
@@ -0,0 +1,171 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pahkat_types::repo::RepoUrl;
+use serde::{Deserialize, Serialize};
+
+use crate::package_store::{InstallTarget, PackageStore};
+use crate::transaction::{PackageAction, PackageStatus, PackageStatusError};
+use crate::PackageKey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LockfileError {
+    #[error("Could not read lockfile: {1}")]
+    Read(#[source] io::Error, PathBuf),
+
+    #[error("Could not write lockfile: {1}")]
+    Write(#[source] io::Error, PathBuf),
+
+    #[error("Could not parse lockfile: {1}")]
+    Parse(#[source] toml::de::Error, PathBuf),
+
+    #[error("Could not serialize lockfile")]
+    Serialize(#[source] toml::ser::Error),
+
+    #[error("Could not determine installed version of `{0}`")]
+    Status(PackageKey, #[source] PackageStatusError),
+
+    #[error("Could not resolve the locked packages")]
+    Candidate(#[from] crate::repo::PackageCandidateError),
+}
+
+/// An installed package, pinned to the exact version that was installed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub key: PackageKey,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub target: InstallTarget,
+}
+
+impl LockedPackage {
+    /// The package key with its version and channel pinned.
+    pub fn pinned_key(&self) -> PackageKey {
+        let mut key = self.key.clone();
+        key.query.version = Some(self.version.clone());
+        key.query.channel = self.channel.clone();
+        key
+    }
+}
+
+/// The set of packages installed in a store, used to reproduce it elsewhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(rename = "package", default)]
+    pub packages: Vec<LockedPackage>,
+}
+
+impl Lockfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Lockfile, LockfileError> {
+        let path = path.as_ref();
+        let file =
+            std::fs::read_to_string(path).map_err(|e| LockfileError::Read(e, path.to_path_buf()))?;
+        toml::from_str(&file).map_err(|e| LockfileError::Parse(e, path.to_path_buf()))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), LockfileError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()?)
+            .map_err(|e| LockfileError::Write(e, path.to_path_buf()))
+    }
+
+    pub fn to_string(&self) -> Result<String, LockfileError> {
+        toml::to_string_pretty(self).map_err(LockfileError::Serialize)
+    }
+
+    /// Builds a lockfile of every package installed in `store` from its configured repositories.
+    ///
+    /// Repositories that have moved are exported under their new URL, as their packages are
+    /// keyed by it even when the config has not been rewritten.
+    pub fn from_store<S: PackageStore + ?Sized>(store: &S) -> Result<Lockfile, LockfileError> {
+        let channels = {
+            let moves = store.moves();
+            let moves = moves.read().unwrap();
+            let config = store.config();
+            let config = config.read().unwrap();
+            let mut channels: Vec<(RepoUrl, Option<String>)> = vec![];
+            for (url, record) in config.repos().iter() {
+                let url = match moves.iter().find(|m| &m.from == url) {
+                    Some(m) => &m.to,
+                    None => url,
+                };
+                if !channels.iter().any(|(x, _)| x == url) {
+                    channels.push((url.clone(), record.channel.clone()));
+                }
+            }
+            channels
+        };
+
+        let mut packages = vec![];
+
+        for (repo_url, channel) in channels.iter() {
+            for target in store.install_targets().iter() {
+                for (id, status) in store.all_statuses(repo_url, *target) {
+                    match status {
+                        Ok(PackageStatus::NotInstalled) | Err(_) => continue,
+                        Ok(_) => {}
+                    }
+
+                    let key = PackageKey::new_unchecked(repo_url.clone(), id, None);
+                    // Synthetic packages are installed outside of pahkat
+                    if crate::synth::is_synthetic(store, &key) {
+                        continue;
+                    }
+
+                    let version = match store.installed_version(&key, *target) {
+                        Ok(Some(v)) => v,
+                        Ok(None) => continue,
+                        Err(e) => return Err(LockfileError::Status(key, e)),
+                    };
+
+                    packages.push(LockedPackage {
+                        key,
+                        version,
+                        channel: channel.clone(),
+                        target: *target,
+                    });
+                }
+            }
+        }
+
+        Ok(Lockfile { packages })
+    }
+
+    /// The actions that take `store` from its current state to the one in this lockfile:
+    /// installing each pinned package, and uninstalling anything not in the lockfile.
+    pub fn actions<S: PackageStore + ?Sized>(
+        &self,
+        store: &S,
+    ) -> Result<Vec<PackageAction>, LockfileError> {
+        let current = Lockfile::from_store(store)?;
+
+        let uninstalls = current
+            .packages
+            .into_iter()
+            .filter(|installed| {
+                !self
+                    .packages
+                    .iter()
+                    .any(|x| x.key == installed.key && x.target == installed.target)
+            })
+            .map(|x| PackageAction::uninstall(x.key, x.target));
+
+        let installs = self.packages.iter().map(|locked| {
+            if let Ok(Some(installed)) = store.installed_version(&locked.key, locked.target) {
+                if installed != locked.version {
+                    log::warn!(
+                        "{} is installed at {} but locked to {}",
+                        &locked.key,
+                        &installed,
+                        &locked.version
+                    );
+                }
+            }
+            PackageAction::install(locked.pinned_key(), locked.target)
+        });
+
+        Ok(uninstalls.chain(installs).collect())
+    }
+}
@@ -0,0 +1,195 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::lockfile::{Lockfile, LockfileError};
+use crate::package_store::{InstallTarget, PackageStore};
+use crate::repo::{PayloadError, ReleaseQuery, VersionQuery};
+use crate::transaction::{PackageAction, PackageStatusError};
+use crate::PackageKey;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ManifestError {
+    #[error("Could not read manifest: {1}")]
+    Read(#[source] io::Error, PathBuf),
+
+    #[error("Could not parse manifest: {1}")]
+    Parse(#[source] toml::de::Error, PathBuf),
+
+    #[error("Invalid version range for `{0}`")]
+    InvalidVersion(String, #[source] semver::ReqParseError),
+
+    #[error("Could not resolve identifier to package key: `{0}`")]
+    UnresolvedId(String),
+
+    #[error("No release of `{0}` matches the manifest")]
+    NoMatchingRelease(String, #[source] PayloadError),
+
+    #[error("Could not determine installed version of `{0}`")]
+    Status(PackageKey, #[source] PackageStatusError),
+
+    #[error("Could not list installed packages")]
+    Installed(#[from] LockfileError),
+
+    #[error("Could not resolve the desired packages")]
+    Candidate(#[from] crate::repo::PackageCandidateError),
+}
+
+/// A package that should be installed, optionally limited to a range of versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesiredPackage {
+    /// A package identifier or full package key.
+    pub id: String,
+    /// A semantic version range, such as `^1.2`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub target: InstallTarget,
+}
+
+/// The packages a store should have installed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    #[serde(rename = "package", default)]
+    pub packages: Vec<DesiredPackage>,
+
+    /// Whether packages not listed in the manifest should be uninstalled.
+    #[serde(default)]
+    pub prune: bool,
+}
+
+/// A difference between the installed packages and a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    pub key: PackageKey,
+    pub target: InstallTarget,
+    pub channel: Option<String>,
+    pub installed: Option<String>,
+    /// The version the manifest resolves to, or `None` if the package should be removed.
+    pub wanted: Option<String>,
+}
+
+impl Manifest {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Manifest, ManifestError> {
+        let path = path.as_ref();
+        let file = std::fs::read_to_string(path)
+            .map_err(|e| ManifestError::Read(e, path.to_path_buf()))?;
+        toml::from_str(&file).map_err(|e| ManifestError::Parse(e, path.to_path_buf()))
+    }
+
+    /// Compares the installed packages against this manifest.
+    ///
+    /// Each listed package should be installed at the newest release matching its version
+    /// range and channel. An empty result means the store is in sync.
+    pub fn drift<S: PackageStore + ?Sized>(&self, store: &S) -> Result<Vec<Drift>, ManifestError> {
+        let mut drift = vec![];
+        let mut wanted_keys = vec![];
+
+        for desired in self.packages.iter() {
+            let (key, wanted) = resolve_desired(store, desired)?;
+            let installed = store
+                .installed_version(&key, desired.target)
+                .map_err(|e| ManifestError::Status(key.clone(), e))?;
+
+            if installed.as_ref() != Some(&wanted) {
+                drift.push(Drift {
+                    key: key.clone(),
+                    target: desired.target,
+                    channel: desired.channel.clone(),
+                    installed,
+                    wanted: Some(wanted),
+                });
+            }
+
+            wanted_keys.push((key, desired.target));
+        }
+
+        if self.prune {
+            let installed = Lockfile::from_store(store)?;
+            for package in installed.packages.into_iter() {
+                if wanted_keys
+                    .iter()
+                    .any(|(key, target)| key == &package.key && *target == package.target)
+                {
+                    continue;
+                }
+
+                drift.push(Drift {
+                    key: package.key,
+                    target: package.target,
+                    channel: package.channel,
+                    installed: Some(package.version),
+                    wanted: None,
+                });
+            }
+        }
+
+        Ok(drift)
+    }
+
+    /// The actions that resolve all drift between the store and this manifest.
+    pub fn actions<S: PackageStore + ?Sized>(
+        &self,
+        store: &S,
+    ) -> Result<Vec<PackageAction>, ManifestError> {
+        Ok(self
+            .drift(store)?
+            .into_iter()
+            .map(|drift| match drift.wanted {
+                Some(version) => {
+                    let mut key = drift.key;
+                    key.query.version = Some(version);
+                    key.query.channel = drift.channel;
+                    PackageAction::install(key, drift.target)
+                }
+                None => PackageAction::uninstall(drift.key, drift.target),
+            })
+            .collect())
+    }
+}
+
+/// Resolves a desired package to its key, without query parameters, and the newest release
+/// version allowed by the manifest.
+fn resolve_desired<S: PackageStore + ?Sized>(
+    store: &S,
+    desired: &DesiredPackage,
+) -> Result<(PackageKey, String), ManifestError> {
+    let key = store
+        .find_package_by_id(&desired.id)
+        .map(|x| x.0.without_query_params())
+        .ok_or_else(|| ManifestError::UnresolvedId(desired.id.clone()))?;
+
+    let range = match desired.version.as_ref() {
+        Some(v) => Some(
+            semver::VersionReq::parse(v)
+                .map_err(|e| ManifestError::InvalidVersion(desired.id.clone(), e))?,
+        ),
+        None => None,
+    };
+
+    let mut query_key = key.clone();
+    query_key.query.channel = desired.channel.clone();
+
+    let repos = store.repos();
+    let repos = repos.read().unwrap();
+    let descriptor = crate::repo::resolve_package(&query_key, &*repos)
+        .map_err(|e| ManifestError::NoMatchingRelease(desired.id.clone(), e))?;
+
+    let mut query = ReleaseQuery::new(&query_key, &*repos);
+    if let Some(range) = range {
+        query.versions = vec![VersionQuery::Semantic(range)];
+    }
+
+    let version = query
+        .iter(&descriptor)
+        .map(|x| &x.release.version)
+        .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .ok_or_else(|| {
+            ManifestError::NoMatchingRelease(desired.id.clone(), PayloadError::NoPayloadFound)
+        })?;
+
+    Ok((key, version.to_string()))
+}
@@ -14,14 +14,14 @@ use serde::de::{self, Deserializer};
 use serde::Deserialize;
 use url::Url;
 
-use super::{PackageStore, SharedRepoErrors, SharedRepos, SharedStoreConfig};
+use super::{PackageStore, SharedRepoErrors, SharedRepoMoves, SharedRepos, SharedStoreConfig};
 use crate::package_store::{ImportError, InstallTarget, LocalizedStrings};
 use crate::repo::{PackageQuery, RepoDownloadError};
 use crate::transaction::{install::InstallError, install::ProcessError, uninstall::UninstallError};
 use crate::transaction::{
     PackageStatus, PackageStatusError, ResolvedDescriptor, ResolvedPackageQuery,
 };
-use crate::{cmp, Config, PackageKey};
+use crate::{cmp, CancellationToken, Config, PackageKey};
 
 #[cfg(target_os = "macos")]
 #[inline(always)]
@@ -54,6 +54,7 @@ struct BundlePlistInfo {
 pub struct MacOSPackageStore {
     repos: SharedRepos,
     errors: SharedRepoErrors,
+    moves: SharedRepoMoves,
     config: SharedStoreConfig,
 }
 
@@ -66,6 +67,10 @@ impl PackageStore for MacOSPackageStore {
         Arc::clone(&self.errors)
     }
 
+    fn moves(&self) -> super::SharedRepoMoves {
+        Arc::clone(&self.moves)
+    }
+
     fn config(&self) -> SharedStoreConfig {
         Arc::clone(&self.config)
     }
@@ -74,6 +79,8 @@ impl PackageStore for MacOSPackageStore {
         &self,
         key: &PackageKey,
         install_target: InstallTarget,
+        cancel: &CancellationToken,
+        _progress: &dyn Fn(u64, u64),
     ) -> Result<PackageStatus, InstallError> {
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
@@ -93,6 +100,11 @@ impl PackageStore for MacOSPackageStore {
             return Err(InstallError::PackageNotInCache);
         }
 
+        // External installers cannot be interrupted once started.
+        if cancel.is_cancelled() {
+            return Err(InstallError::UserCancelled);
+        }
+
         install_macos_package(&pkg_path, install_target).map_err(InstallError::InstallerFailure)?;
 
         Ok(self
@@ -132,6 +144,7 @@ impl PackageStore for MacOSPackageStore {
     fn download(
         &self,
         key: &PackageKey,
+        cancel: CancellationToken,
     ) -> std::pin::Pin<
         Box<
             dyn futures::stream::Stream<Item = crate::package_store::DownloadEvent>
@@ -142,7 +155,7 @@ impl PackageStore for MacOSPackageStore {
     > {
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
-        crate::repo::download(&self.config, key, &query, &*repos)
+        crate::repo::download(&self.config, key, &query, &*repos, cancel)
     }
 
     fn status(
@@ -150,6 +163,10 @@ impl PackageStore for MacOSPackageStore {
         key: &PackageKey,
         install_target: InstallTarget,
     ) -> Result<PackageStatus, PackageStatusError> {
+        if let Some(status) = crate::synth::status(self, key, install_target) {
+            return status;
+        }
+
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
 
@@ -163,6 +180,28 @@ impl PackageStore for MacOSPackageStore {
         self.status_impl(&descriptor, &release, install_target)
     }
 
+    fn installed_version(
+        &self,
+        key: &PackageKey,
+        install_target: InstallTarget,
+    ) -> Result<Option<String>, PackageStatusError> {
+        if let Some(version) = crate::synth::installed_version(self, key, install_target) {
+            return version;
+        }
+
+        let repos = self.repos.read().unwrap();
+        let query = crate::repo::ReleaseQuery::new(key, &*repos);
+
+        let (target, _, descriptor) = crate::repo::resolve_payload(key, &query, &*repos)
+            .map_err(PackageStatusError::Payload)?;
+        match target.payload {
+            pahkat_types::payload::Payload::MacOSPackage(_) => {}
+            _ => return Err(PackageStatusError::WrongPayloadType),
+        };
+
+        Ok(self.installed_version_impl(&descriptor, install_target))
+    }
+
     fn all_statuses(
         &self,
         repo_url: &RepoUrl,
@@ -185,10 +224,14 @@ impl PackageStore for MacOSPackageStore {
         &self,
     ) -> crate::package_store::Future<Result<(), HashMap<RepoUrl, RepoDownloadError>>> {
         let config = self.config().read().unwrap().clone();
+        let shared_config = self.config();
         let repos = self.repos();
+        let moves = self.moves();
         Box::pin(async move {
-            let (result, errors) = crate::repo::refresh_repos(config).await;
+            let (result, errors, found_moves) = crate::repo::refresh_repos(config).await;
             *repos.write().unwrap() = result;
+            crate::repo::record_moves(&shared_config, &found_moves);
+            *moves.write().unwrap() = found_moves;
             if errors.is_empty() {
                 Ok(())
             } else {
@@ -207,8 +250,9 @@ impl PackageStore for MacOSPackageStore {
     ) -> crate::package_store::Future<HashMap<RepoUrl, LocalizedStrings>> {
         let repos = self.repos.read().unwrap();
         let urls = repos.keys().cloned().collect::<Vec<_>>();
+        let cache_dir = self.config.read().unwrap().settings().repo_cache_dir();
 
-        Box::pin(crate::repo::strings(urls, language))
+        Box::pin(crate::repo::strings(urls, language, cache_dir))
     }
 
     fn resolve_package_query(
@@ -228,12 +272,17 @@ impl MacOSPackageStore {
         let store = MacOSPackageStore {
             repos: Arc::new(RwLock::new(HashMap::new())),
             errors: Arc::new(RwLock::new(HashMap::new())),
+            moves: Arc::new(RwLock::new(vec![])),
             config: Arc::new(RwLock::new(config)),
         };
 
         // We ignore errors here.
         let _ = store.refresh_repos().await;
 
+        if let Ok(Some(_)) = store.interrupted_transaction() {
+            log::warn!("An interrupted transaction was found; it can be resumed or rolled back.");
+        }
+
         store
     }
 
@@ -243,6 +292,17 @@ impl MacOSPackageStore {
         release: &pahkat_types::package::Release,
         target: InstallTarget,
     ) -> Result<PackageStatus, PackageStatusError> {
+        match self.installed_version_impl(descriptor, target) {
+            Some(installed) => self::cmp::cmp(&installed, &release.version),
+            None => Ok(PackageStatus::NotInstalled),
+        }
+    }
+
+    fn installed_version_impl(
+        &self,
+        descriptor: &pahkat_types::package::Descriptor,
+        target: InstallTarget,
+    ) -> Option<String> {
         // Map over all targets to find pkg_ids
         let pkg_ids: Vec<&str> = descriptor.release.iter().fold(vec![], |acc, release| {
             release.target.iter().fold(acc, |mut acc, target| {
@@ -273,19 +333,12 @@ impl MacOSPackageStore {
                 }
             });
 
-        let pkg_info = match pkg_info {
-            Some(v) => v,
-            None => return Ok(PackageStatus::NotInstalled),
-        };
-
-        let status = self::cmp::cmp(&pkg_info.pkg_version, &release.version);
-
-        status
+        pkg_info.map(|x| x.pkg_version)
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct MacOSPackageExportPath {
+pub(crate) struct MacOSPackageExportPath {
     pub gid: u64,
     #[serde(rename = "install-time")]
     pub install_time: u64,
@@ -297,7 +350,7 @@ struct MacOSPackageExportPath {
 }
 
 #[derive(Debug, Deserialize)]
-struct MacOSPackageExportPlist {
+pub(crate) struct MacOSPackageExportPlist {
     #[serde(rename = "install-location")]
     pub install_location: String,
     #[serde(rename = "install-time")]
@@ -322,7 +375,7 @@ impl MacOSPackageExportPlist {
     }
 }
 
-fn get_package_info(
+pub(crate) fn get_package_info(
     bundle_id: &str,
     target: InstallTarget,
 ) -> Result<MacOSPackageExportPlist, ProcessError> {
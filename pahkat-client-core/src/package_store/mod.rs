@@ -17,15 +17,19 @@ use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::config::Config;
-use crate::repo::{PackageQuery, RepoDownloadError};
+use crate::lockfile::{Lockfile, LockfileError};
+use crate::repo::{PackageQuery, RepoDownloadError, RepoMove};
+use crate::transaction::history::{HistoryEntry, HistoryError, HistoryLog};
+use crate::transaction::journal::{JournalError, TransactionJournal};
 use crate::transaction::{install::InstallError, uninstall::UninstallError};
 use crate::transaction::{PackageStatus, PackageStatusError, ResolvedPackageQuery};
 use crate::types::repo::RepoUrl;
-use crate::{LoadedRepository, PackageKey};
+use crate::{CancellationToken, LoadedRepository, PackageKey};
 
 pub type SharedStoreConfig = Arc<RwLock<Config>>;
 pub type SharedRepos = Arc<RwLock<HashMap<RepoUrl, LoadedRepository>>>;
 pub type SharedRepoErrors = Arc<RwLock<HashMap<RepoUrl, RepoDownloadError>>>;
+pub type SharedRepoMoves = Arc<RwLock<Vec<RepoMove>>>;
 
 #[derive(Debug, thiserror::Error)]
 pub enum ImportError {
@@ -74,6 +78,40 @@ impl From<u8> for InstallTarget {
     }
 }
 
+/// An installed package with a newer release available.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageUpdate {
+    pub key: PackageKey,
+    pub target: InstallTarget,
+    pub installed_version: String,
+    pub candidate_version: String,
+    pub download_size: u64,
+    pub is_reboot_required: bool,
+    pub channel: Option<String>,
+}
+
+/// Whether a package was asked for, or only installed to satisfy another package.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallReason {
+    Requested,
+    Dependency,
+    /// The store does not keep track of why packages were installed.
+    Unknown,
+}
+
+/// Why a package is installed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseDependencies {
+    pub key: PackageKey,
+    pub target: InstallTarget,
+    pub reason: InstallReason,
+    /// The installed packages that depend on this package.
+    pub dependents: Vec<PackageKey>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 #[non_exhaustive]
@@ -82,6 +120,15 @@ pub struct LocalizedStrings {
     pub channels: HashMap<String, String>,
 }
 
+impl From<crate::types::repo::Localisation> for LocalizedStrings {
+    fn from(strings: crate::types::repo::Localisation) -> Self {
+        LocalizedStrings {
+            tags: strings.tags.into_iter().collect(),
+            channels: strings.channels.into_iter().collect(),
+        }
+    }
+}
+
 impl Default for InstallTarget {
     fn default() -> Self {
         InstallTarget::System
@@ -94,17 +141,25 @@ pub type Future<T> = Pin<Box<dyn std::future::Future<Output = T> + Send + Sync +
 pub trait PackageStore: Send + Sync {
     fn repos(&self) -> SharedRepos;
     fn errors(&self) -> SharedRepoErrors;
+    /// The repositories found to have moved by the last refresh.
+    fn moves(&self) -> SharedRepoMoves;
     fn config(&self) -> SharedStoreConfig;
 
     #[must_use]
-    fn download(&self, key: &PackageKey) -> Stream<DownloadEvent>;
+    fn download(&self, key: &PackageKey, cancel: CancellationToken) -> Stream<DownloadEvent>;
 
     fn import(&self, key: &PackageKey, installer_path: &Path) -> Result<PathBuf, ImportError>;
 
+    /// Installs a previously downloaded package.
+    ///
+    /// Stores that can measure their own progress call `progress` with the number of bytes
+    /// installed so far and the expected total.
     fn install(
         &self,
         key: &PackageKey,
         target: InstallTarget,
+        cancel: &CancellationToken,
+        progress: &dyn Fn(u64, u64),
     ) -> Result<PackageStatus, InstallError>;
 
     fn uninstall(
@@ -119,6 +174,13 @@ pub trait PackageStore: Send + Sync {
         target: InstallTarget,
     ) -> Result<PackageStatus, PackageStatusError>;
 
+    /// The version of the package currently installed, if any.
+    fn installed_version(
+        &self,
+        key: &PackageKey,
+        target: InstallTarget,
+    ) -> Result<Option<String>, PackageStatusError>;
+
     fn all_statuses(
         &self,
         repo_url: &RepoUrl,
@@ -142,6 +204,106 @@ pub trait PackageStore: Send + Sync {
 
     fn strings(&self, language: String) -> Future<HashMap<RepoUrl, LocalizedStrings>>;
 
+    /// Lists every installed package, for any install target, that has a newer release
+    /// available in the channel it follows.
+    fn updates(&self) -> Result<Vec<PackageUpdate>, PackageStatusError> {
+        crate::repo::updates(self)
+    }
+
+    /// Records why a package was installed. Stores that do not keep track of this
+    /// ignore it.
+    fn set_install_reason(
+        &self,
+        _key: &PackageKey,
+        _target: InstallTarget,
+        _reason: InstallReason,
+    ) -> Result<(), PackageStatusError> {
+        Ok(())
+    }
+
+    /// Explains why a package is installed: whether it was requested, and which installed
+    /// packages depend on it according to the repositories.
+    fn reverse_dependencies(
+        &self,
+        key: &PackageKey,
+        target: InstallTarget,
+    ) -> Result<ReverseDependencies, PackageStatusError> {
+        Ok(ReverseDependencies {
+            key: key.clone().without_query_params(),
+            target,
+            reason: InstallReason::Unknown,
+            dependents: crate::repo::installed_dependents(self, key, target)?,
+        })
+    }
+
+    /// Moves what the store recorded about a package installed as `from` to `to`, after the
+    /// package was renamed or moved and `to` has been installed. Stores that detect installed
+    /// packages from the system have nothing to migrate.
+    fn migrate_package(
+        &self,
+        _from: &PackageKey,
+        _to: &PackageKey,
+        _target: InstallTarget,
+    ) -> Result<(), UninstallError> {
+        Ok(())
+    }
+
+    /// The directory packages are installed into, if the store manages one itself.
+    fn install_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// The install targets this store tells apart. Stores that report the same status for
+    /// every target list only one, so installed packages are not reported twice.
+    fn install_targets(&self) -> &'static [InstallTarget] {
+        &[InstallTarget::System, InstallTarget::User]
+    }
+
+    /// The packages installed for `target` from the configured repositories. Stores that keep
+    /// no record of what they installed evaluate the status of every package to find them.
+    fn installed_packages(
+        &self,
+        target: InstallTarget,
+    ) -> Result<Vec<PackageKey>, PackageStatusError> {
+        let repo_urls = {
+            let repos = self.repos();
+            let repos = repos.read().unwrap();
+            repos.keys().cloned().collect::<Vec<_>>()
+        };
+
+        let mut installed = vec![];
+        for repo_url in repo_urls.iter() {
+            for (id, status) in self.all_statuses(repo_url, target) {
+                match status {
+                    Ok(PackageStatus::NotInstalled) | Err(_) => continue,
+                    Ok(_) => installed.push(PackageKey::new_unchecked(repo_url.clone(), id, None)),
+                }
+            }
+        }
+
+        Ok(installed)
+    }
+
+    /// Lists every installed package, pinned to its installed version.
+    fn lockfile(&self) -> Result<Lockfile, LockfileError> {
+        Lockfile::from_store(self)
+    }
+
+    /// Records a change to an installed package in the store's history.
+    fn record_history(&self, entry: &HistoryEntry) -> Result<(), HistoryError> {
+        HistoryLog::append(&*self.config().read().unwrap(), entry)
+    }
+
+    /// Every recorded change to installed packages, oldest first.
+    fn history(&self) -> Result<Vec<HistoryEntry>, HistoryError> {
+        HistoryLog::load(&*self.config().read().unwrap())
+    }
+
+    /// Returns the journal of a transaction that did not run to completion, if any.
+    fn interrupted_transaction(&self) -> Result<Option<TransactionJournal>, JournalError> {
+        TransactionJournal::load(&*self.config().read().unwrap())
+    }
+
     // #[export::experimental]
     fn resolve_package_query(
         &self,
@@ -11,11 +11,14 @@ use pahkat_types::repo::RepoUrl;
 use r2d2_sqlite::SqliteConnectionManager;
 use xz2::bufread::XzDecoder;
 
-use super::InstallTarget;
-use crate::package_store::{SharedRepoErrors, SharedRepos, SharedStoreConfig};
+use super::{InstallReason, InstallTarget, ReverseDependencies};
+use crate::package_store::{SharedRepoErrors, SharedRepoMoves, SharedRepos, SharedStoreConfig};
 use crate::repo::RepoDownloadError;
 use crate::transaction::{
-    install::InstallError, uninstall::UninstallError, PackageDependencyError, ResolvedPackageQuery,
+    history::{HistoryEntry, HistoryError},
+    install::InstallError,
+    uninstall::UninstallError,
+    PackageDependencyError, ResolvedPackageQuery,
 };
 use crate::{
     cmp,
@@ -25,18 +28,30 @@ use crate::{
     repo::{LoadedRepository, PackageQuery},
     transaction::PackageStatusError,
     transaction::{PackageStatus, ResolvedDescriptor},
-    Config, PackageKey, PackageStore,
+    CancellationToken, Config, PackageKey, PackageStore,
 };
 
 // type Result<T> = std::result::Result<T, Error>;
 
 const SQL_INIT: &str = include_str!("prefix/prefix_init.sql");
+const SQL_MIGRATIONS: &[(i64, &str)] = &[
+    (2, include_str!("prefix/prefix_migrate_2.sql")),
+    (3, include_str!("prefix/prefix_migrate_3.sql")),
+    (4, include_str!("prefix/prefix_migrate_4.sql")),
+];
+
+mod hooks;
+mod shims;
+
+use self::hooks::HookContext;
+use pahkat_types::payload::tarball::Hook;
 
 pub struct PrefixPackageStore {
     pool: r2d2::Pool<SqliteConnectionManager>,
     prefix: PathBuf,
     repos: SharedRepos,
     errors: SharedRepoErrors,
+    moves: SharedRepoMoves,
     config: SharedStoreConfig,
 }
 
@@ -88,12 +103,14 @@ impl PrefixPackageStore {
         let pool = Self::make_pool(manager)?;
         let conn = pool.get()?;
         conn.execute_batch(SQL_INIT)?;
+        Self::migrate(&conn)?;
 
         let store = PrefixPackageStore {
             pool,
             prefix: prefix_path,
             repos: Default::default(),
             errors: Default::default(),
+            moves: Default::default(),
             config: Arc::new(RwLock::new(config)),
         };
 
@@ -115,18 +132,24 @@ impl PrefixPackageStore {
         log::debug!("{:?}", &db_file_path);
         let manager = SqliteConnectionManager::file(&db_file_path);
         let pool = Self::make_pool(manager)?;
+        Self::migrate(&*pool.get()?)?;
 
         let store = PrefixPackageStore {
             pool,
             prefix: prefix_path,
             repos: Default::default(),
             errors: Default::default(),
+            moves: Default::default(),
             config: Arc::new(RwLock::new(config)),
         };
 
         // We ignore failures here.
         let _ = store.refresh_repos().await;
 
+        if let Ok(Some(_)) = store.interrupted_transaction() {
+            log::warn!("An interrupted transaction was found; it can be resumed or rolled back.");
+        }
+
         Ok(store)
     }
 
@@ -141,12 +164,61 @@ impl PrefixPackageStore {
             .build(manager)
     }
 
+    /// Applies any schema migrations newer than the database's schema version.
+    fn migrate(conn: &rusqlite::Connection) -> Result<(), rusqlite::Error> {
+        let version: i64 = conn.query_row(
+            "SELECT MAX(schema_version) FROM meta",
+            rusqlite::NO_PARAMS,
+            |row| row.get(0),
+        )?;
+
+        for (migration_version, sql) in SQL_MIGRATIONS.iter() {
+            if *migration_version > version {
+                log::debug!(
+                    "Migrating package database to version {}",
+                    migration_version
+                );
+                conn.execute_batch(sql)?;
+            }
+        }
+
+        Ok(())
+    }
+
     fn package_db_path(config: &Config) -> PathBuf {
         config.settings().config_dir().join("packages.sqlite")
     }
 
-    fn package_dir(&self, package_id: &str) -> PathBuf {
-        self.prefix.join("pkg").join(package_id)
+    /// The root that packages for the given target are installed under. System packages
+    /// live in the prefix itself, and user packages in its `user` directory.
+    fn target_root(&self, target: InstallTarget) -> PathBuf {
+        match target {
+            InstallTarget::System => self.prefix.clone(),
+            InstallTarget::User => self.prefix.join("user"),
+        }
+    }
+
+    fn package_dir(&self, package_id: &str, target: InstallTarget) -> PathBuf {
+        self.target_root(target).join("pkg").join(package_id)
+    }
+
+    /// The tarball payload currently in the repositories for `key`, used to find the hooks
+    /// of an installed package.
+    fn tarball_payload(&self, key: &PackageKey) -> Option<pahkat_types::payload::tarball::Package> {
+        let repos = self.repos.read().unwrap();
+        let query =
+            crate::repo::ReleaseQuery::new(key, &*repos).and_payloads(vec!["TarballPackage"]);
+
+        match crate::repo::resolve_payload(key, &query, &*repos) {
+            Ok((target, _, _)) => match target.payload {
+                pahkat_types::payload::Payload::TarballPackage(v) => Some(v),
+                _ => None,
+            },
+            Err(e) => {
+                log::warn!("Could not resolve hooks for {}: {:?}", key, e);
+                None
+            }
+        }
     }
 }
 
@@ -178,10 +250,18 @@ impl PackageStore for PrefixPackageStore {
         Arc::clone(&self.errors)
     }
 
+    fn moves(&self) -> super::SharedRepoMoves {
+        Arc::clone(&self.moves)
+    }
+
     fn config(&self) -> super::SharedStoreConfig {
         Arc::clone(&self.config)
     }
 
+    fn install_dir(&self) -> Option<PathBuf> {
+        Some(self.prefix.clone())
+    }
+
     fn import(&self, key: &PackageKey, installer_path: &Path) -> Result<PathBuf, ImportError> {
         log::debug!("IMPORTING");
         let repos = self.repos.read().unwrap();
@@ -192,6 +272,7 @@ impl PackageStore for PrefixPackageStore {
     fn download(
         &self,
         key: &PackageKey,
+        cancel: CancellationToken,
     ) -> std::pin::Pin<
         Box<
             dyn futures::stream::Stream<Item = crate::package_store::DownloadEvent>
@@ -202,13 +283,15 @@ impl PackageStore for PrefixPackageStore {
     > {
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
-        crate::repo::download(&self.config, key, &query, &*repos)
+        crate::repo::download(&self.config, key, &query, &*repos, cancel)
     }
 
     fn install(
         &self,
         key: &PackageKey,
-        target: InstallTarget,
+        install_target: InstallTarget,
+        cancel: &CancellationToken,
+        progress: &dyn Fn(u64, u64),
     ) -> Result<PackageStatus, InstallError> {
         log::trace!("In prefix install");
 
@@ -231,108 +314,196 @@ impl PackageStore for PrefixPackageStore {
             return Err(InstallError::PackageNotInCache);
         }
 
-        let file = File::open(&pkg_path).unwrap();
+        let file = File::open(&pkg_path)
+            .map_err(|e| InstallError::ReadPackageFailed(e, pkg_path.clone()))?;
         let reader = XzDecoder::new(std::io::BufReader::new(file));
+        let total_size = installer.installed_size;
+        let mut extracted_size = 0u64;
 
         let mut tar_file = tar::Archive::new(reader);
         let mut files = vec![];
 
-        let pkg_path = self.package_dir(&package.package.id);
-        create_dir_all(&pkg_path).unwrap(); // map_err(InstallError::CreateDirFailed)?;
+        let root = self.target_root(install_target);
+        let pkg_path = self.package_dir(&package.package.id, install_target);
+        create_dir_all(&pkg_path)
+            .map_err(|e| InstallError::CreateDirFailed(e, pkg_path.clone()))?;
+
+        log::debug!("Prefix: {:?}, root: {:?}", &self.prefix, &root);
 
-        log::debug!("Prefix: {:?}", &self.prefix);
+        // Unpack into a staging directory first, so that cancelling part way through
+        // leaves any currently installed version untouched.
+        let staging_dir = tempfile::Builder::new()
+            .prefix(".staging-")
+            .tempdir_in(root.join("pkg"))
+            .map_err(|e| InstallError::CreateDirFailed(e, root.join("pkg")))?;
 
-        for entry in tar_file.entries().unwrap() {
-            let mut entry = entry.unwrap();
-            let unpack_res;
-            {
-                unpack_res = entry.unpack_in(&pkg_path).unwrap(); //.context(UnpackFailed)?;
+        for entry in tar_file.entries().map_err(InstallError::UnpackFailed)? {
+            if cancel.is_cancelled() {
+                log::debug!("Install of {} cancelled", &key);
+                return Err(InstallError::UserCancelled);
             }
 
+            let mut entry = entry.map_err(InstallError::UnpackFailed)?;
+            let unpack_res = entry
+                .unpack_in(staging_dir.path())
+                .map_err(InstallError::UnpackFailed)?;
+
+            // The installed size is only a hint from the index, so never report past it
+            extracted_size += entry.header().size().unwrap_or(0);
+            progress(extracted_size.min(total_size), total_size);
+
             if unpack_res {
-                let entry_path = entry.header().path().unwrap();
+                let entry_path = entry.path().map_err(InstallError::UnpackFailed)?;
                 log::debug!("entry path: {:?}", &entry_path);
-                let entry_path = entry_path.to_str().unwrap().to_string();
+                let entry_path = match entry_path.to_str() {
+                    Some(v) => v.to_string(),
+                    None => return Err(InstallError::InvalidFilePath(entry_path.into_owned())),
+                };
                 files.push(entry_path);
             } else {
                 continue;
             }
         }
 
-        let deps = &target.dependencies;
-        let dependencies: Vec<String> = deps.keys().map(|x| x.to_owned()).collect();
+        let version = release.version.to_string();
+        let context = HookContext {
+            prefix: &root,
+            package_id: &key.id,
+            version: &version,
+        };
 
+        hooks::run(&installer, Hook::PreInstall, staging_dir.path(), &context)?;
+
+        // Dependencies may be given by identifier, but are recorded by their full key.
+        let dependencies: Vec<String> = target
+            .dependencies
+            .keys()
+            .map(|x| match crate::repo::dependency_key(self, x) {
+                Some(key) => key.to_string(),
+                None => x.to_owned(),
+            })
+            .collect();
+
+        // Record the package before moving its files into place, so that whatever is moved
+        // can always be found and uninstalled.
         {
             let record = PackageDbRecord {
                 id: 0,
                 url: key.clone().without_query_params().to_string(),
-                version: release.version.to_string(),
-                files,
+                target: install_target,
+                version: version.clone(),
+                files: files.clone(),
                 dependencies,
             };
 
-            let mut conn = self.pool.get().unwrap();
-            record.save(&mut conn).unwrap();
+            let mut conn = self.pool.get().map_err(InstallError::DatabaseConnection)?;
+            record.save(&mut conn).map_err(InstallError::Database)?;
         };
 
+        for file in &files {
+            let src = staging_dir.path().join(file);
+            let dest = pkg_path.join(file);
+
+            let meta = src.symlink_metadata().map_err(InstallError::UnpackFailed)?;
+            if meta.is_dir() {
+                create_dir_all(&dest)
+                    .map_err(|e| InstallError::CreateDirFailed(e, dest.clone()))?;
+                continue;
+            }
+
+            if let Some(parent) = dest.parent() {
+                create_dir_all(parent)
+                    .map_err(|e| InstallError::CreateDirFailed(e, parent.to_path_buf()))?;
+            }
+            std::fs::rename(&src, &dest).map_err(InstallError::UnpackFailed)?;
+        }
+
+        shims::link_executables(&root, &key.id, &pkg_path, &installer)?;
+        shims::write_activation_script(&root)
+            .map_err(|(e, path)| InstallError::ActivationScriptFailed(e, path))?;
+
+        // The package is recorded as installed even if this fails, so it can be uninstalled.
+        hooks::run(&installer, Hook::PostInstall, &pkg_path, &context)?;
+
         Ok(PackageStatus::UpToDate)
     }
 
     fn uninstall(
         &self,
         key: &PackageKey,
-        _target: InstallTarget,
+        target: InstallTarget,
     ) -> Result<PackageStatus, UninstallError> {
-        let mut conn = self.pool.get().unwrap();
-        let record = match PackageDbRecord::find_by_id(&mut conn, &key) {
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(UninstallError::DatabaseConnection)?;
+        let record = match PackageDbRecord::find_by_id(&mut conn, &key, target)
+            .map_err(UninstallError::Database)?
+        {
             None => return Err(UninstallError::NotInstalled),
             Some(v) => v,
         };
 
-        let pkg_path = self.package_dir(&key.id);
-        for file in &record.files {
-            let file = match pkg_path.join(file).canonicalize() {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
+        let root = self.target_root(target);
+        let pkg_path = self.package_dir(&key.id, target);
+
+        // Hooks are those of the installed release, not whatever the index now offers
+        let mut installed_key = key.clone();
+        installed_key.query.version = Some(record.version.clone());
+        let payload = self.tarball_payload(&installed_key);
+        let context = HookContext {
+            prefix: &root,
+            package_id: &key.id,
+            version: &record.version,
+        };
 
-            if file.is_dir() {
-                continue;
-            }
+        let post_uninstall = match &payload {
+            Some(payload) => hooks::skip_missing(hooks::preserve_script(
+                payload,
+                Hook::PostUninstall,
+                &pkg_path,
+            ))?,
+            None => None,
+        };
 
-            if file.exists() {
-                remove_file(file).unwrap();
+        if let Some(payload) = &payload {
+            let script =
+                hooks::skip_missing(hooks::script_path(payload, Hook::PreUninstall, &pkg_path))?;
+            if let Some(script) = script {
+                hooks::run_script(&script, payload, Hook::PreUninstall, &pkg_path, &context)?;
             }
         }
 
-        for file in &record.files {
-            let file = match pkg_path.join(file).canonicalize() {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
+        shims::unlink_executables(&root, &key.id)?;
+        remove_files(&pkg_path, &record.files)?;
 
-            if !file.is_dir() {
-                continue;
-            }
+        record.delete(&mut conn).map_err(UninstallError::Database)?;
+        shims::write_activation_script(&root)
+            .map_err(|(e, path)| UninstallError::ActivationScriptFailed(e, path))?;
 
-            let dir = read_dir(&file).unwrap();
-            if dir.count() == 0 {
-                remove_dir(&file).unwrap();
-            }
+        if let (Some(payload), Some((_dir, script))) = (&payload, &post_uninstall) {
+            hooks::run_script(script, payload, Hook::PostUninstall, &root, &context)?;
         }
 
-        record.delete(&mut conn).unwrap();
-
         Ok(PackageStatus::NotInstalled)
     }
 
     fn status(
         &self,
         key: &PackageKey,
-        _target: InstallTarget,
+        target: InstallTarget,
     ) -> Result<PackageStatus, PackageStatusError> {
-        let mut conn = self.pool.get().unwrap();
-        let record = match PackageDbRecord::find_by_id(&mut conn, &key) {
+        if let Some(status) = crate::synth::status(self, key, target) {
+            return status;
+        }
+
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+        let record = match PackageDbRecord::find_by_id(&mut conn, &key, target)
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?
+        {
             None => return Ok(PackageStatus::NotInstalled),
             Some(v) => v,
         };
@@ -356,6 +527,154 @@ impl PackageStore for PrefixPackageStore {
         status
     }
 
+    fn installed_version(
+        &self,
+        key: &PackageKey,
+        target: InstallTarget,
+    ) -> Result<Option<String>, PackageStatusError> {
+        if let Some(version) = crate::synth::installed_version(self, key, target) {
+            return version;
+        }
+
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+        let record = PackageDbRecord::find_by_id(&mut conn, &key, target)
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+        Ok(record.map(|x| x.version))
+    }
+
+    fn installed_packages(
+        &self,
+        target: InstallTarget,
+    ) -> Result<Vec<PackageKey>, PackageStatusError> {
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+        PackageDbConnection(&mut conn)
+            .installed(target)
+            .map_err(|e| PackageStatusError::Database(e.to_string()))
+    }
+
+    fn migrate_package(
+        &self,
+        from: &PackageKey,
+        to: &PackageKey,
+        target: InstallTarget,
+    ) -> Result<(), UninstallError> {
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(UninstallError::DatabaseConnection)?;
+        let record = match PackageDbRecord::find_by_id(&mut conn, from, target)
+            .map_err(UninstallError::Database)?
+        {
+            None => return Ok(()),
+            Some(v) => v,
+        };
+
+        // The package lives on under its new key, so only its old files are removed and no
+        // uninstall hooks are run. When the id is unchanged, the new install shares the
+        // package directory and shims, and whatever it installed is kept.
+        let root = self.target_root(target);
+        if from.id == to.id {
+            let kept = PackageDbRecord::find_by_id(&mut conn, to, target)
+                .map_err(UninstallError::Database)?
+                .map(|x| x.files)
+                .unwrap_or_default();
+            let files = record
+                .files
+                .iter()
+                .filter(|x| !kept.contains(x))
+                .cloned()
+                .collect::<Vec<_>>();
+            remove_files(&self.package_dir(&from.id, target), &files)?;
+        } else {
+            shims::unlink_executables(&root, &from.id)?;
+            remove_files(&self.package_dir(&from.id, target), &record.files)?;
+        }
+
+        let to = to.clone().without_query_params().to_string();
+        PackageDbConnection(&mut conn)
+            .migrate_pkg(&record, &to)
+            .map_err(UninstallError::Database)?;
+        shims::write_activation_script(&root)
+            .map_err(|(e, path)| UninstallError::ActivationScriptFailed(e, path))?;
+
+        Ok(())
+    }
+
+    fn set_install_reason(
+        &self,
+        key: &PackageKey,
+        target: InstallTarget,
+        reason: InstallReason,
+    ) -> Result<(), PackageStatusError> {
+        let is_dependent = match reason {
+            InstallReason::Requested => false,
+            InstallReason::Dependency => true,
+            InstallReason::Unknown => return Ok(()),
+        };
+
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+        let url = key.clone().without_query_params().to_string();
+        PackageDbConnection(&mut conn)
+            .set_dependent(&url, target, is_dependent)
+            .map_err(|e| PackageStatusError::Database(e.to_string()))
+    }
+
+    fn reverse_dependencies(
+        &self,
+        key: &PackageKey,
+        target: InstallTarget,
+    ) -> Result<ReverseDependencies, PackageStatusError> {
+        let key = key.clone().without_query_params();
+
+        // Every installed package records its dependencies, so there is no need to look
+        // through the repositories.
+        let (reason, dependents) = {
+            let mut conn = self
+                .pool
+                .get()
+                .map_err(|e| PackageStatusError::Database(e.to_string()))?;
+            let conn = PackageDbConnection(&mut conn);
+            conn.reverse_dependencies(&key.to_string(), target)
+                .map_err(|e| PackageStatusError::Database(e.to_string()))?
+        };
+
+        Ok(ReverseDependencies {
+            key,
+            target,
+            reason,
+            dependents,
+        })
+    }
+
+    fn record_history(&self, entry: &HistoryEntry) -> Result<(), HistoryError> {
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| HistoryError::Database(e.to_string()))?;
+        PackageDbConnection(&mut conn)
+            .append_history(entry)
+            .map_err(|e| HistoryError::Database(e.to_string()))
+    }
+
+    fn history(&self) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let mut conn = self
+            .pool
+            .get()
+            .map_err(|e| HistoryError::Database(e.to_string()))?;
+        PackageDbConnection(&mut conn)
+            .history()
+            .map_err(|e| HistoryError::Database(e.to_string()))
+    }
+
     fn all_statuses(
         &self,
         repo_url: &RepoUrl,
@@ -378,10 +697,25 @@ impl PackageStore for PrefixPackageStore {
         &self,
     ) -> crate::package_store::Future<Result<(), HashMap<RepoUrl, RepoDownloadError>>> {
         let config = self.config().read().unwrap().clone();
+        let shared_config = self.config();
         let repos = self.repos();
+        let moves = self.moves();
+        let pool = self.pool.clone();
         Box::pin(async move {
-            let (result, errors) = crate::repo::refresh_repos(config).await;
+            let (result, errors, found_moves) = crate::repo::refresh_repos(config).await;
             *repos.write().unwrap() = result;
+            crate::repo::record_moves(&shared_config, &found_moves);
+            for m in found_moves.iter() {
+                let result = pool.get().map_err(|e| e.to_string()).and_then(|mut conn| {
+                    PackageDbConnection(&mut conn)
+                        .move_repo(&m.from, &m.to)
+                        .map_err(|e| e.to_string())
+                });
+                if let Err(e) = result {
+                    log::error!("Could not move packages of {} to {}: {}", &m.from, &m.to, e);
+                }
+            }
+            *moves.write().unwrap() = found_moves;
             if errors.is_empty() {
                 Ok(())
             } else {
@@ -401,8 +735,9 @@ impl PackageStore for PrefixPackageStore {
     {
         let repos = self.repos.read().unwrap();
         let urls = repos.keys().cloned().collect::<Vec<_>>();
+        let cache_dir = self.config.read().unwrap().settings().repo_cache_dir();
 
-        Box::pin(crate::repo::strings(urls, language))
+        Box::pin(crate::repo::strings(urls, language, cache_dir))
     }
 
     fn resolve_package_query(
@@ -420,53 +755,100 @@ impl PackageStore for PrefixPackageStore {
 struct PackageDbRecord {
     id: i64,
     url: String,
+    target: InstallTarget,
     version: String,
     files: Vec<String>,
     dependencies: Vec<String>,
 }
 
+/// Removes the recorded files of a package, then any of its directories left empty.
+fn remove_files(pkg_path: &Path, files: &[String]) -> Result<(), UninstallError> {
+    for file in files {
+        let file = match pkg_path.join(file).canonicalize() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if file.is_dir() {
+            continue;
+        }
+
+        if file.exists() {
+            remove_file(&file).map_err(|e| UninstallError::RemoveFileFailed(e, file.clone()))?;
+        }
+    }
+
+    for file in files {
+        let file = match pkg_path.join(file).canonicalize() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if !file.is_dir() {
+            continue;
+        }
+
+        let dir = read_dir(&file).map_err(|e| UninstallError::RemoveDirFailed(e, file.clone()))?;
+        if dir.count() == 0 {
+            remove_dir(&file).map_err(|e| UninstallError::RemoveDirFailed(e, file.clone()))?;
+        }
+    }
+
+    Ok(())
+}
+
 struct PackageDbConnection<'a>(&'a mut rusqlite::Connection);
 
 impl<'a> PackageDbConnection<'a> {
-    fn dependencies(&self, url: &str) -> Vec<String> {
+    fn dependencies(&self, id: i64) -> rusqlite::Result<Vec<String>> {
         let mut stmt = self
             .0
-            .prepare("SELECT dependency_id FROM packages_dependencies WHERE package_id = (SELECT id FROM packages WHERE url = ?)")
-            .unwrap();
-
-        let res = stmt
-            .query_map(&[&url], |row| row.get(0))
-            .unwrap()
-            .map(|x| x.unwrap())
-            .collect();
+            .prepare("SELECT DISTINCT url FROM packages WHERE url IN (SELECT dependency_url FROM packages_dependencies WHERE package_id = ?)")?;
 
+        let res = stmt.query_map(&[&id], |row| row.get(0))?.collect();
         res
     }
 
-    fn files(&self, url: &str) -> Vec<String> {
+    fn files(&self, id: i64) -> rusqlite::Result<Vec<String>> {
         let mut stmt = self
             .0
-            .prepare("SELECT file_path FROM packages_files WHERE package_id = (SELECT id FROM packages WHERE url = ?)")
-            .expect("prepared statement");
-
-        let res = stmt
-            .query_map(&[&url], |row| row.get(0))
-            .expect("query_map succeeds")
-            .map(|x: Result<String, _>| x.unwrap())
-            .collect();
+            .prepare("SELECT file_path FROM packages_files WHERE package_id = ?")?;
 
+        let res = stmt.query_map(&[&id], |row| row.get(0))?.collect();
         res
     }
 
-    fn version(&self, url: &str) -> Option<String> {
-        match self.0.query_row(
-            "SELECT version FROM packages WHERE url = ? LIMIT 1",
-            &[&url],
-            |row| row.get(0),
-        ) {
-            Ok(v) => v,
-            Err(_) => return None,
-        }
+    /// The keys of the packages installed for `target`.
+    fn installed(&self, target: InstallTarget) -> rusqlite::Result<Vec<PackageKey>> {
+        use std::convert::TryFrom;
+
+        let mut stmt = self
+            .0
+            .prepare("SELECT url FROM packages WHERE target = ?")?;
+        let urls = stmt
+            .query_map(&[&target.to_u8()], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(urls
+            .iter()
+            .filter_map(|url| PackageKey::try_from(&**url).ok())
+            .collect())
+    }
+
+    fn id_and_version(
+        &self,
+        url: &str,
+        target: InstallTarget,
+    ) -> rusqlite::Result<Option<(i64, String)>> {
+        use rusqlite::OptionalExtension;
+
+        self.0
+            .query_row_named(
+                "SELECT id, version FROM packages WHERE url = :url AND target = :target LIMIT 1",
+                &[(":url", &url), (":target", &target.to_u8())],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
     }
 
     fn replace_pkg(&mut self, pkg: &PackageDbRecord) -> rusqlite::Result<()> {
@@ -474,47 +856,45 @@ impl<'a> PackageDbConnection<'a> {
         let utc: DateTime<Utc> = Utc::now();
         let utc = format!("{:?}", utc);
 
-        let tx = self.0.transaction().unwrap();
+        let tx = self.0.transaction()?;
+
+        let target = pkg.target.to_u8();
 
         tx.execute_named(
-            "INSERT INTO packages(url, version, installed_on, updated_on)
-            VALUES (:url, :version, :installed_on, :updated_on)
-            ON CONFLICT(url) DO UPDATE SET
+            "INSERT INTO packages(url, target, version, installed_on, updated_on)
+            VALUES (:url, :target, :version, :installed_on, :updated_on)
+            ON CONFLICT(url, target) DO UPDATE SET
                 version=excluded.version,
                 updated_on=excluded.updated_on",
             &[
                 // (":id", &pkg.id),
                 (":url", &pkg.url),
+                (":target", &target),
                 (":version", &pkg.version),
                 (":installed_on", &utc),
                 (":updated_on", &utc),
             ],
-        )
-        .unwrap();
-        let id: i64;
-        {
-            let mut stmt = tx
-                .prepare("SELECT id FROM packages WHERE url = :url")
-                .unwrap();
-            let mut rows = stmt.query_named(&[(":url", &pkg.url)]).unwrap();
-            id = rows.next().unwrap().unwrap().get(0).unwrap()
-        }
+        )?;
+        let id: i64 = tx.query_row_named(
+            "SELECT id FROM packages WHERE url = :url AND target = :target",
+            &[(":url", &pkg.url), (":target", &target)],
+            |row| row.get(0),
+        )?;
 
         log::trace!("Row id: {}", id);
         tx.execute(
             "DELETE FROM packages_dependencies WHERE package_id = ?",
             &[id],
-        )
-        .unwrap();
-        tx.execute("DELETE FROM packages_files WHERE package_id = ?", &[id])
-            .unwrap();
+        )?;
+        tx.execute("DELETE FROM packages_files WHERE package_id = ?", &[id])?;
 
         {
-            let mut dep_stmt = tx
-                .prepare(
-                    "INSERT INTO packages_dependencies(package_id, dependency_id) VALUES (:id, (SELECT id FROM packages WHERE url = :dep_url))",
-                )
-                .unwrap();
+            // Edges are kept by URL, as a dependency may be installed for the other target,
+            // provided outside of this prefix, or reinstalled later under a new row id.
+            let mut dep_stmt = tx.prepare(
+                "INSERT OR IGNORE INTO packages_dependencies(package_id, dependency_url)
+                VALUES (:id, :dep_url)",
+            )?;
             for dep_url in &pkg.dependencies {
                 dep_stmt.execute_named(&[(":id", &id), (":dep_url", &*dep_url)])?;
             }
@@ -523,17 +903,221 @@ impl<'a> PackageDbConnection<'a> {
                 .prepare("INSERT INTO packages_files(package_id, file_path) VALUES (:id, :path)")?;
 
             for file_path in &pkg.files {
-                file_stmt
-                    .execute_named(&[(":id", &id), (":path", &file_path.as_str())])
-                    .unwrap();
+                file_stmt.execute_named(&[(":id", &id), (":path", &file_path.as_str())])?;
             }
         }
 
         tx.commit()
     }
 
+    fn set_dependent(
+        &self,
+        url: &str,
+        target: InstallTarget,
+        is_dependent: bool,
+    ) -> rusqlite::Result<()> {
+        self.0.execute_named(
+            "UPDATE packages SET is_dependent = :is_dependent WHERE url = :url AND target = :target",
+            &[
+                (":is_dependent", &is_dependent),
+                (":url", &url),
+                (":target", &target.to_u8()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Why the package is installed, and the installed packages recorded as depending on it.
+    fn reverse_dependencies(
+        &self,
+        url: &str,
+        target: InstallTarget,
+    ) -> rusqlite::Result<(InstallReason, Vec<PackageKey>)> {
+        use rusqlite::OptionalExtension;
+        use std::convert::TryFrom;
+
+        let row: Option<bool> = self
+            .0
+            .query_row_named(
+                "SELECT is_dependent FROM packages WHERE url = :url AND target = :target LIMIT 1",
+                &[(":url", &url), (":target", &target.to_u8())],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let is_dependent = match row {
+            Some(v) => v,
+            None => return Ok((InstallReason::Unknown, vec![])),
+        };
+
+        let reason = if is_dependent {
+            InstallReason::Dependency
+        } else {
+            InstallReason::Requested
+        };
+
+        // A dependent relies on the record for its own target, or on this one if the
+        // dependency is not installed for its target at all
+        let mut stmt = self.0.prepare(
+            "SELECT DISTINCT p.url FROM packages p
+            JOIN packages_dependencies d ON d.package_id = p.id
+            WHERE d.dependency_url = :url AND (p.target = :target OR NOT EXISTS (
+                SELECT 1 FROM packages WHERE url = :url AND target = p.target
+            ))",
+        )?;
+        let urls = stmt
+            .query_map_named(&[(":url", &url), (":target", &target.to_u8())], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let dependents = urls
+            .iter()
+            .filter_map(|url| PackageKey::try_from(&**url).ok())
+            .collect();
+
+        Ok((reason, dependents))
+    }
+
+    fn append_history(&self, entry: &HistoryEntry) -> rusqlite::Result<()> {
+        self.0.execute_named(
+            "INSERT INTO history(timestamp, url, target, event, from_version, to_version, initiator)
+            VALUES (:timestamp, :url, :target, :event, :from_version, :to_version, :initiator)",
+            &[
+                (":timestamp", &entry.timestamp),
+                (":url", &entry.key.to_string()),
+                (":target", &entry.target.to_u8()),
+                (":event", &entry.event.as_str()),
+                (":from_version", &entry.from_version),
+                (":to_version", &entry.to_version),
+                (":initiator", &entry.initiator.as_str()),
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn history(&self) -> rusqlite::Result<Vec<HistoryEntry>> {
+        use std::convert::TryFrom;
+
+        let mut stmt = self.0.prepare(
+            "SELECT timestamp, url, target, event, from_version, to_version, initiator
+            FROM history ORDER BY id",
+        )?;
+
+        let rows = stmt.query_map(rusqlite::NO_PARAMS, |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u8>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })?;
+
+        let mut entries = vec![];
+        for row in rows {
+            let (timestamp, url, target, event, from_version, to_version, initiator) = row?;
+
+            let (key, event) = match (PackageKey::try_from(&*url), event.parse()) {
+                (Ok(key), Ok(event)) => (key, event),
+                _ => {
+                    log::warn!("Skipping invalid history entry for {}", &url);
+                    continue;
+                }
+            };
+
+            entries.push(HistoryEntry {
+                timestamp,
+                key,
+                target: InstallTarget::from(target),
+                event,
+                from_version,
+                to_version,
+                initiator: initiator.parse().unwrap_or_default(),
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Moves the install reason and dependents of `pkg` to the package recorded as `url`,
+    /// then removes `pkg`.
+    fn migrate_pkg(&mut self, pkg: &PackageDbRecord, url: &str) -> rusqlite::Result<()> {
+        let tx = self.0.transaction()?;
+        let target = pkg.target.to_u8();
+
+        tx.execute_named(
+            "UPDATE packages SET is_dependent = (SELECT is_dependent FROM packages WHERE id = :old_id)
+            WHERE url = :url AND target = :target",
+            &[(":old_id", &pkg.id), (":url", &url), (":target", &target)],
+        )?;
+        tx.execute_named(
+            "UPDATE OR IGNORE packages_dependencies SET dependency_url = :url
+            WHERE dependency_url = :old_url
+            AND package_id IN (SELECT id FROM packages WHERE target = :target)",
+            &[(":old_url", &pkg.url), (":url", &url), (":target", &target)],
+        )?;
+
+        tx.execute(
+            "DELETE FROM packages_dependencies WHERE package_id = ?",
+            &[&pkg.id],
+        )?;
+        tx.execute(
+            "DELETE FROM packages_files WHERE package_id = ?",
+            &[&pkg.id],
+        )?;
+        tx.execute("DELETE FROM packages WHERE id = ?", &[&pkg.id])?;
+
+        tx.commit()
+    }
+
+    /// Rewrites the packages, dependencies and history recorded for the repository at
+    /// `from` to the one it has moved to.
+    fn move_repo(&mut self, from: &RepoUrl, to: &RepoUrl) -> rusqlite::Result<()> {
+        use std::convert::TryFrom;
+
+        let tx = self.0.transaction()?;
+        let urls = {
+            let mut stmt = tx.prepare(
+                "SELECT url FROM packages
+                UNION SELECT dependency_url FROM packages_dependencies
+                UNION SELECT url FROM history",
+            )?;
+            let urls = stmt
+                .query_map(rusqlite::NO_PARAMS, |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            urls
+        };
+
+        for url in urls {
+            let mut key = match PackageKey::try_from(&*url) {
+                Ok(v) if &v.repository_url == from => v,
+                _ => continue,
+            };
+            key.repository_url = to.clone();
+            let new_url = key.to_string();
+            let params: &[(&str, &dyn rusqlite::ToSql)] = &[(":from", &url), (":to", &new_url)];
+
+            // A package already recorded under the new URL keeps that record
+            tx.execute_named(
+                "UPDATE OR IGNORE packages SET url = :to WHERE url = :from",
+                params,
+            )?;
+            tx.execute_named(
+                "UPDATE OR IGNORE packages_dependencies SET dependency_url = :to
+                WHERE dependency_url = :from",
+                params,
+            )?;
+            tx.execute_named("UPDATE history SET url = :to WHERE url = :from", params)?;
+        }
+
+        tx.commit()
+    }
+
     fn remove_pkg(&mut self, pkg: &PackageDbRecord) -> rusqlite::Result<()> {
-        let tx = self.0.transaction().unwrap();
+        let tx = self.0.transaction()?;
 
         tx.execute("DELETE FROM packages WHERE id = ?", &[&pkg.id])?;
         tx.execute(
@@ -553,25 +1137,27 @@ impl PackageDbRecord {
     pub fn find_by_id(
         conn: &mut rusqlite::Connection,
         key: &PackageKey,
-    ) -> Option<PackageDbRecord> {
+        target: InstallTarget,
+    ) -> rusqlite::Result<Option<PackageDbRecord>> {
         let conn = PackageDbConnection(conn);
         let url = key.clone().without_query_params().to_string();
 
-        let version = match conn.version(&url) {
+        let (id, version) = match conn.id_and_version(&url, target)? {
             Some(v) => v,
-            None => return None,
+            None => return Ok(None),
         };
 
-        let files = conn.files(&url);
-        let dependencies = conn.dependencies(&url);
+        let files = conn.files(id)?;
+        let dependencies = conn.dependencies(id)?;
 
-        Some(PackageDbRecord {
-            id: 0,
+        Ok(Some(PackageDbRecord {
+            id,
             url,
+            target,
             version,
             files,
             dependencies,
-        })
+        }))
     }
 
     pub fn save(&self, conn: &mut rusqlite::Connection) -> rusqlite::Result<()> {
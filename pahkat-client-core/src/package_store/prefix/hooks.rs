@@ -0,0 +1,141 @@
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use pahkat_types::payload::tarball::{Hook, Package};
+
+use crate::transaction::install::HookError;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Details of the package a hook runs for, passed to the script as environment variables.
+pub(super) struct HookContext<'a> {
+    pub prefix: &'a Path,
+    pub package_id: &'a str,
+    pub version: &'a str,
+}
+
+/// Resolves the script declared for `hook` within `package_dir`, if any.
+///
+/// Scripts must be relative paths inside the package, so an index cannot point a hook
+/// at an arbitrary executable on the system.
+pub(super) fn script_path(
+    package: &Package,
+    hook: Hook,
+    package_dir: &Path,
+) -> Result<Option<PathBuf>, HookError> {
+    let script = match package.hook(hook) {
+        Some(v) => Path::new(v),
+        None => return Ok(None),
+    };
+
+    if !script
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(HookError::InvalidPath(
+            hook.to_string(),
+            script.to_path_buf(),
+        ));
+    }
+
+    let path = package_dir.join(script);
+    if !path.is_file() {
+        return Err(HookError::NotFound(hook.to_string(), path));
+    }
+
+    Ok(Some(path))
+}
+
+/// Treats a declared script that is missing from the package as if none were declared,
+/// so a broken uninstall hook cannot stop a package from being removed.
+pub(super) fn skip_missing<T>(
+    result: Result<Option<T>, HookError>,
+) -> Result<Option<T>, HookError> {
+    match result {
+        Err(HookError::NotFound(hook, path)) => {
+            log::warn!("Skipping {} hook, script not found: {:?}", hook, path);
+            Ok(None)
+        }
+        result => result,
+    }
+}
+
+/// Runs the script declared for `hook` from `package_dir`, if there is one.
+pub(super) fn run(
+    package: &Package,
+    hook: Hook,
+    package_dir: &Path,
+    context: &HookContext<'_>,
+) -> Result<(), HookError> {
+    match script_path(package, hook, package_dir)? {
+        Some(script) => run_script(&script, package, hook, package_dir, context),
+        None => Ok(()),
+    }
+}
+
+/// Runs `script` with a cleared environment, stopping it if it exceeds the hook timeout.
+pub(super) fn run_script(
+    script: &Path,
+    package: &Package,
+    hook: Hook,
+    working_dir: &Path,
+    context: &HookContext<'_>,
+) -> Result<(), HookError> {
+    let timeout = package.hook_timeout.unwrap_or(DEFAULT_TIMEOUT_SECS);
+    log::debug!("Running {} hook: {:?}", hook, script);
+
+    let mut command = Command::new(script);
+    command
+        .current_dir(working_dir)
+        .env_clear()
+        .env("PAHKAT_HOOK", hook.as_str())
+        .env("PAHKAT_PREFIX", context.prefix)
+        .env("PAHKAT_PACKAGE_ID", context.package_id)
+        .env("PAHKAT_PACKAGE_VERSION", context.version)
+        .env("PAHKAT_PACKAGE_DIR", working_dir)
+        .stdin(Stdio::null());
+
+    if let Some(path) = std::env::var_os("PATH") {
+        command.env("PATH", path);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| HookError::Spawn(hook.to_string(), e))?;
+
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(HookError::Failed(hook.to_string(), status)),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(HookError::TimedOut(hook.to_string(), timeout));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(HookError::Spawn(hook.to_string(), e)),
+        }
+    }
+}
+
+/// Copies the script for `hook` out of `package_dir`, for hooks that run after the
+/// package's own files have been removed.
+pub(super) fn preserve_script(
+    package: &Package,
+    hook: Hook,
+    package_dir: &Path,
+) -> Result<Option<(tempfile::TempDir, PathBuf)>, HookError> {
+    let script = match script_path(package, hook, package_dir)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    let dir = tempfile::tempdir().map_err(|e| HookError::Spawn(hook.to_string(), e))?;
+    let dest = dir.path().join(script.file_name().unwrap_or_default());
+    std::fs::copy(&script, &dest).map_err(|e| HookError::Spawn(hook.to_string(), e))?;
+
+    Ok(Some((dir, dest)))
+}
@@ -0,0 +1,27 @@
+BEGIN;
+
+-- Packages are now recorded per install target, so a URL is only unique per target.
+CREATE TABLE packages_new (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    url             TEXT NOT NULL,
+    target          INTEGER NOT NULL DEFAULT 0,
+    version         TEXT NOT NULL,
+    installed_on    TEXT NOT NULL,
+    updated_on      TEXT NOT NULL,
+    is_dependent    BOOLEAN NOT NULL DEFAULT 0,
+    is_pegged       BOOLEAN NOT NULL DEFAULT 0,
+
+    UNIQUE (url, target)
+);
+
+INSERT INTO packages_new(id, url, target, version, installed_on, updated_on, is_dependent, is_pegged)
+    SELECT id, url, 0, version, installed_on, updated_on, is_dependent, is_pegged FROM packages;
+
+DROP TABLE packages;
+ALTER TABLE packages_new RENAME TO packages;
+
+CREATE INDEX idx_packages_url ON packages (url);
+
+UPDATE meta SET schema_version = 2;
+
+COMMIT;
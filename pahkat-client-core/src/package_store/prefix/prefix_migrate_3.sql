@@ -0,0 +1,19 @@
+BEGIN;
+
+-- An append-only log of every change to an installed package.
+CREATE TABLE history (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp       TEXT NOT NULL,
+    url             TEXT NOT NULL,
+    target          INTEGER NOT NULL DEFAULT 0,
+    event           TEXT NOT NULL,
+    from_version    TEXT,
+    to_version      TEXT,
+    initiator       TEXT NOT NULL
+);
+
+CREATE INDEX idx_history_url ON history (url);
+
+UPDATE meta SET schema_version = 3;
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+-- Dependencies are recorded by URL, so dependents stay linked when a dependency is
+-- uninstalled and installed again under a new row id.
+CREATE TABLE packages_dependencies_new (
+    package_id      INTEGER NOT NULL,
+    dependency_url  TEXT NOT NULL,
+
+    PRIMARY KEY (package_id, dependency_url),
+    FOREIGN KEY (package_id) REFERENCES packages(id)
+);
+
+INSERT OR IGNORE INTO packages_dependencies_new(package_id, dependency_url)
+    SELECT d.package_id, p.url FROM packages_dependencies d
+    JOIN packages p ON p.id = d.dependency_id;
+
+DROP TABLE packages_dependencies;
+ALTER TABLE packages_dependencies_new RENAME TO packages_dependencies;
+
+CREATE INDEX idx_packages_dependencies_url ON packages_dependencies (dependency_url);
+
+UPDATE meta SET schema_version = 4;
+
+COMMIT;
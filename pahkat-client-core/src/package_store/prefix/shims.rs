@@ -0,0 +1,140 @@
+use std::fs::{create_dir_all, read_dir, remove_file};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use pahkat_types::payload::tarball::Package;
+
+use crate::transaction::{install::InstallError, uninstall::UninstallError};
+
+const ACTIVATION_SCRIPT: &str = "env.sh";
+
+fn bin_dir(prefix: &Path) -> PathBuf {
+    prefix.join("bin")
+}
+
+#[cfg(unix)]
+fn shim_path(prefix: &Path, executable: &Path) -> Option<PathBuf> {
+    executable.file_name().map(|x| bin_dir(prefix).join(x))
+}
+
+#[cfg(windows)]
+fn shim_path(prefix: &Path, executable: &Path) -> Option<PathBuf> {
+    executable
+        .file_stem()
+        .map(|x| bin_dir(prefix).join(x).with_extension("cmd"))
+}
+
+/// The package directory relative to the `bin` directory, so the prefix can be moved.
+fn relative_package_dir(package_id: &str) -> PathBuf {
+    Path::new("..").join("pkg").join(package_id)
+}
+
+#[cfg(unix)]
+fn create_shim(target: &Path, shim: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, shim)
+}
+
+#[cfg(windows)]
+fn create_shim(target: &Path, shim: &Path) -> io::Result<()> {
+    std::fs::write(shim, format!("@\"%~dp0{}\" %*\r\n", target.display()))
+}
+
+/// Whether `shim` was created for an executable of the given package.
+#[cfg(unix)]
+fn is_shim_for(shim: &Path, package_id: &str) -> bool {
+    match std::fs::read_link(shim) {
+        Ok(target) => target.starts_with(relative_package_dir(package_id)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(windows)]
+fn is_shim_for(shim: &Path, package_id: &str) -> bool {
+    let package_dir = relative_package_dir(package_id).join("");
+    match std::fs::read_to_string(shim) {
+        Ok(content) => content.contains(&*package_dir.to_string_lossy()),
+        Err(_) => false,
+    }
+}
+
+/// Creates a shim in the prefix's `bin` directory for each executable the package declares.
+///
+/// An existing shim of the same name is replaced, as the most recently installed package wins.
+pub(super) fn link_executables(
+    prefix: &Path,
+    package_id: &str,
+    package_dir: &Path,
+    package: &Package,
+) -> Result<(), InstallError> {
+    if package.executables.is_empty() {
+        return Ok(());
+    }
+
+    let bin_dir = bin_dir(prefix);
+    create_dir_all(&bin_dir).map_err(|e| InstallError::CreateDirFailed(e, bin_dir.clone()))?;
+
+    for executable in package.executables.iter() {
+        let executable = Path::new(executable);
+        let is_relative = executable
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        let shim = match shim_path(prefix, executable) {
+            Some(v) if is_relative => v,
+            _ => return Err(InstallError::InvalidFilePath(executable.to_path_buf())),
+        };
+
+        if !package_dir.join(executable).is_file() {
+            return Err(InstallError::InvalidFilePath(package_dir.join(executable)));
+        }
+        let target = relative_package_dir(package_id).join(executable);
+
+        if shim.symlink_metadata().is_ok() {
+            log::warn!("Replacing existing executable shim: {:?}", &shim);
+            remove_file(&shim).map_err(|e| InstallError::LinkFailed(e, shim.clone()))?;
+        }
+
+        create_shim(&target, &shim).map_err(|e| InstallError::LinkFailed(e, shim.clone()))?;
+    }
+
+    Ok(())
+}
+
+/// Removes the shims of the given package, leaving those of other packages alone.
+pub(super) fn unlink_executables(prefix: &Path, package_id: &str) -> Result<(), UninstallError> {
+    let bin_dir = bin_dir(prefix);
+    let entries = match read_dir(&bin_dir) {
+        Ok(v) => v,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(UninstallError::RemoveDirFailed(e, bin_dir)),
+    };
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| UninstallError::RemoveDirFailed(e, bin_dir.clone()))?
+            .path();
+
+        if is_shim_for(&path, package_id) {
+            remove_file(&path).map_err(|e| UninstallError::RemoveFileFailed(e, path.clone()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes `env.sh` to the prefix, which adds the prefix's `bin` directory to `PATH` when
+/// sourced from a shell.
+pub(super) fn write_activation_script(prefix: &Path) -> Result<(), (io::Error, PathBuf)> {
+    let absolute = prefix
+        .canonicalize()
+        .unwrap_or_else(|_| prefix.to_path_buf());
+    let quoted = absolute.to_string_lossy().replace('\'', "'\\''");
+    let script = format!(
+        "# Generated by pahkat. Source this file to use the packages in this prefix.\n\
+         export PAHKAT_PREFIX='{}'\n\
+         export PATH=\"$PAHKAT_PREFIX/bin:$PATH\"\n",
+        quoted
+    );
+
+    let path = prefix.join(ACTIVATION_SCRIPT);
+    std::fs::write(&path, script).map_err(|e| (e, path))
+}
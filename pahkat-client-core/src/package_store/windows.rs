@@ -19,7 +19,7 @@ use crate::transaction::{
     PackageStatusError, ResolvedDescriptor, ResolvedPackageQuery,
 };
 use crate::Config;
-use crate::{repo::PayloadError, LoadedRepository, PackageKey, PackageStore};
+use crate::{repo::PayloadError, CancellationToken, LoadedRepository, PackageKey, PackageStore};
 use pahkat_types::{
     package::{Descriptor, Package},
     payload::windows,
@@ -31,12 +31,13 @@ const DISPLAY_VERSION: &'static str = "DisplayVersion";
 const QUIET_UNINSTALL_STRING: &'static str = "QuietUninstallString";
 
 use super::LocalizedStrings;
-use super::{SharedRepoErrors, SharedRepos, SharedStoreConfig};
+use super::{SharedRepoErrors, SharedRepoMoves, SharedRepos, SharedStoreConfig};
 
 #[derive(Debug)]
 pub struct WindowsPackageStore {
     repos: SharedRepos,
     errors: SharedRepoErrors,
+    moves: SharedRepoMoves,
     config: SharedStoreConfig,
 }
 
@@ -57,10 +58,19 @@ impl PackageStore for WindowsPackageStore {
         Arc::clone(&self.config)
     }
 
+    fn install_targets(&self) -> &'static [InstallTarget] {
+        // Installed packages are found the same way whatever the target
+        &[InstallTarget::System]
+    }
+
     fn errors(&self) -> super::SharedRepoErrors {
         Arc::clone(&self.errors)
     }
 
+    fn moves(&self) -> super::SharedRepoMoves {
+        Arc::clone(&self.moves)
+    }
+
     fn repos(&self) -> SharedRepos {
         Arc::clone(&self.repos)
     }
@@ -68,6 +78,7 @@ impl PackageStore for WindowsPackageStore {
     fn download(
         &self,
         key: &PackageKey,
+        cancel: CancellationToken,
     ) -> std::pin::Pin<
         Box<
             dyn futures::stream::Stream<Item = crate::package_store::DownloadEvent>
@@ -78,13 +89,15 @@ impl PackageStore for WindowsPackageStore {
     > {
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
-        crate::repo::download(&self.config, key, &query, &*repos)
+        crate::repo::download(&self.config, key, &query, &*repos, cancel)
     }
 
     fn install(
         &self,
         key: &PackageKey,
         install_target: InstallTarget,
+        cancel: &CancellationToken,
+        _progress: &dyn Fn(u64, u64),
     ) -> Result<PackageStatus, InstallError> {
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
@@ -104,6 +117,11 @@ impl PackageStore for WindowsPackageStore {
             return Err(InstallError::PackageNotInCache);
         }
 
+        // External installers cannot be interrupted once started.
+        if cancel.is_cancelled() {
+            return Err(InstallError::UserCancelled);
+        }
+
         let mut args: Vec<OsString> = match (&installer.kind, &installer.args) {
             (_, &Some(ref v)) => sys::args(&v).map(|x| x.clone()).collect(),
             (&Some(ref type_), &None) => {
@@ -248,6 +266,10 @@ impl PackageStore for WindowsPackageStore {
     ) -> Result<PackageStatus, PackageStatusError> {
         log::debug!("status: {}, target: {:?}", &key.to_string(), install_target);
 
+        if let Some(status) = crate::synth::status(self, key, install_target) {
+            return status;
+        }
+
         let repos = self.repos.read().unwrap();
         let query = crate::repo::ReleaseQuery::new(key, &*repos);
 
@@ -261,6 +283,30 @@ impl PackageStore for WindowsPackageStore {
         self.status_impl(key, &descriptor, install_target)
     }
 
+    fn installed_version(
+        &self,
+        key: &PackageKey,
+        install_target: InstallTarget,
+    ) -> Result<Option<String>, PackageStatusError> {
+        if let Some(version) = crate::synth::installed_version(self, key, install_target) {
+            return version;
+        }
+
+        let repos = self.repos.read().unwrap();
+        let query = crate::repo::ReleaseQuery::new(key, &*repos);
+
+        let (target, _, descriptor) = crate::repo::resolve_payload(key, &query, &*repos)
+            .map_err(PackageStatusError::Payload)?;
+        match target.payload {
+            pahkat_types::payload::Payload::WindowsExecutable(_) => {}
+            _ => return Err(PackageStatusError::WrongPayloadType),
+        };
+
+        Ok(self
+            .installed_version_impl(key, &descriptor)?
+            .map(|(installed, _)| installed))
+    }
+
     fn find_package_by_key(&self, key: &PackageKey) -> Option<Package> {
         let repos = self.repos.read().unwrap();
         crate::repo::find_package_by_key(key, &*repos)
@@ -275,10 +321,14 @@ impl PackageStore for WindowsPackageStore {
         &self,
     ) -> crate::package_store::Future<Result<(), HashMap<RepoUrl, RepoDownloadError>>> {
         let config = self.config().read().unwrap().clone();
+        let shared_config = self.config();
         let repos = self.repos();
+        let moves = self.moves();
         Box::pin(async move {
-            let (result, errors) = crate::repo::refresh_repos(config).await;
+            let (result, errors, found_moves) = crate::repo::refresh_repos(config).await;
             *repos.write().unwrap() = result;
+            crate::repo::record_moves(&shared_config, &found_moves);
+            *moves.write().unwrap() = found_moves;
             if errors.is_empty() {
                 Ok(())
             } else {
@@ -311,8 +361,9 @@ impl PackageStore for WindowsPackageStore {
     ) -> crate::package_store::Future<HashMap<RepoUrl, LocalizedStrings>> {
         let repos = self.repos.read().unwrap();
         let urls = repos.keys().cloned().collect::<Vec<_>>();
+        let cache_dir = self.config.read().unwrap().settings().repo_cache_dir();
 
-        Box::pin(crate::repo::strings(urls, language))
+        Box::pin(crate::repo::strings(urls, language, cache_dir))
     }
 
     fn resolve_package_query(
@@ -331,12 +382,17 @@ impl WindowsPackageStore {
         let store = WindowsPackageStore {
             repos: Default::default(),
             errors: Default::default(),
+            moves: Default::default(),
             config: Arc::new(RwLock::new(config)),
         };
 
         // We ignore errors here.
         let _ = store.refresh_repos().await;
 
+        if let Ok(Some(_)) = store.interrupted_transaction() {
+            log::warn!("An interrupted transaction was found; it can be resumed or rolled back.");
+        }
+
         store
     }
 
@@ -350,6 +406,23 @@ impl WindowsPackageStore {
         package: &Descriptor,
         _target: InstallTarget,
     ) -> Result<PackageStatus, PackageStatusError> {
+        let (installed, candidate) = match self.installed_version_impl(key, package)? {
+            Some(v) => v,
+            None => return Ok(PackageStatus::NotInstalled),
+        };
+
+        let status = crate::cmp::cmp(&installed, &candidate);
+
+        log::debug!("Status: {:?}", &status);
+        status
+    }
+
+    /// Returns the installed version along with the version of the matching candidate release.
+    fn installed_version_impl(
+        &self,
+        key: &PackageKey,
+        package: &Descriptor,
+    ) -> Result<Option<(String, pahkat_types::package::Version)>, PackageStatusError> {
         let repos = self.repos.read().unwrap();
         let mut query = crate::repo::ReleaseQuery::new(key, &*repos);
 
@@ -362,7 +435,7 @@ impl WindowsPackageStore {
             .find_map(|(x, v)| uninstall_regkey(&v).map(|i| (x, i)))
         {
             Some(v) => v,
-            None => return Ok(PackageStatus::NotInstalled),
+            None => return Ok(None),
         };
 
         let disp_version: String = match inst_key.get_value(DISPLAY_VERSION) {
@@ -370,9 +443,6 @@ impl WindowsPackageStore {
             Ok(v) => v,
         };
 
-        let status = crate::cmp::cmp(&disp_version, &response.release.version);
-
-        log::debug!("Status: {:?}", &status);
-        status
+        Ok(Some((disp_version, response.release.version.clone())))
     }
 }
@@ -1,8 +1,11 @@
 mod repository;
+mod strings;
 
 pub use pahkat_types::PackageKey;
-pub use repository::{LoadedRepository, RepoDownloadError};
+pub use repository::{LoadedRepository, RepoDownloadError, RepoMove};
 
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 use std::path::Path;
@@ -27,6 +30,7 @@ use crate::transaction::{
 use pahkat_types::package::{Descriptor, Package, Release, Version};
 use pahkat_types::payload::Target;
 use pahkat_types::repo::RepoUrl;
+use pahkat_types::{Arch, Platform};
 
 #[derive(Debug, Clone, Error)]
 pub enum PayloadError {
@@ -36,17 +40,20 @@ pub enum PayloadError {
     NoConcretePackage,
     #[error("No payload found meeting query criteria")]
     NoPayloadFound,
-    #[error("Some criteria is not met for the current payload")]
+    #[error("No payload meets the criteria: {0}")]
     CriteriaUnmet(String),
 }
 
 #[derive(Debug, Clone)]
 pub struct ReleaseQuery<'a> {
-    pub platform: &'a str,
-    pub arch: Option<&'a str>,
+    pub platform: Platform,
+    pub arch: Option<Arch>,
     pub channels: Vec<&'a str>,
     pub versions: Vec<VersionQuery<'a>>,
     pub payloads: Vec<&'a str>,
+    /// The OS version targets must support. Targets with OS version bounds never match if
+    /// it is unknown.
+    pub os_version: Option<&'a str>,
 }
 
 impl<'a> ReleaseQuery<'a> {
@@ -54,16 +61,119 @@ impl<'a> ReleaseQuery<'a> {
         self.payloads = payloads;
         self
     }
+
+    /// Whether a release in `channel` is visible to this query. Releases without a channel
+    /// are always visible.
+    pub(crate) fn accepts_channel(&self, channel: Option<&str>) -> bool {
+        match channel {
+            Some(channel) => self.channels.contains(&channel),
+            None => true,
+        }
+    }
+
+    /// How well a target for `platform` and `arch` suits this query, lower being better, or
+    /// `None` if it does not match. Targets for the native architecture come first, then
+    /// those for any architecture, then those for compatible architectures.
+    pub(crate) fn target_rank(&self, platform: &Platform, arch: Option<&Arch>) -> Option<usize> {
+        if platform != &self.platform {
+            return None;
+        }
+
+        let target_arch = match arch {
+            Some(v) => v,
+            None => return Some(1),
+        };
+
+        // Without an architecture in the query, rank by the one this was built for
+        let native = match self.arch.as_ref() {
+            Some(v) => Cow::Borrowed(v),
+            None => Cow::Owned(Arch::from(std::env::consts::ARCH)),
+        };
+
+        match native.preference(&self.platform, target_arch)? {
+            0 => Some(0),
+            fallback => Some(fallback + 1),
+        }
+    }
+
+    /// Whether the OS version of this query is within the bounds of `target`, or why not.
+    pub(crate) fn check_os_version(&self, target: &Target) -> Result<(), String> {
+        let (min, max) = (&target.min_os_version, &target.max_os_version);
+        if min.is_none() && max.is_none() {
+            return Ok(());
+        }
+
+        let os_version = match self.os_version {
+            Some(v) => v,
+            None => return Err("the OS version could not be detected".into()),
+        };
+        let version = os_version_parts(os_version);
+
+        if let Some(min) = min {
+            if cmp_os_version(&version, &os_version_parts(min)) == Ordering::Less {
+                return Err(format!(
+                    "requires OS version {} or newer, but this is {}",
+                    min, os_version
+                ));
+            }
+        }
+
+        if let Some(max) = max {
+            if cmp_os_version(&version, &os_version_parts(max)) == Ordering::Greater {
+                return Err(format!(
+                    "requires OS version {} or older, but this is {}",
+                    max, os_version
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The numeric components of an OS version, such as `[10, 15, 7]` for `10.15.7`. Components
+/// end at the first one not starting with a digit, and suffixes are ignored, so
+/// `5.4.0-42-generic` gives `[5, 4, 0]`.
+fn os_version_parts(version: &str) -> Vec<u64> {
+    let mut parts = vec![];
+
+    for part in version.trim().split('.') {
+        let digits = part.len() - part.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        match part[..digits].parse() {
+            Ok(v) => parts.push(v),
+            Err(_) => break,
+        }
+        if digits < part.len() {
+            break;
+        }
+    }
+
+    parts
+}
+
+/// Compares `version` to `bound` only as far as the bound is given, so `10.15.7` is equal
+/// to `10.15`. Missing components of `version` count as zero.
+fn cmp_os_version(version: &[u64], bound: &[u64]) -> Ordering {
+    for (index, bound) in bound.iter().enumerate() {
+        let part = version.get(index).copied().unwrap_or(0);
+        match part.cmp(bound) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+
+    Ordering::Equal
 }
 
 impl<'a> Default for ReleaseQuery<'a> {
     fn default() -> Self {
         Self {
-            platform: defaults::platform(),
-            arch: defaults::arch(),
+            platform: Platform::from(defaults::platform()),
+            arch: defaults::arch().map(Arch::from),
             channels: vec![],
             versions: vec![],
             payloads: defaults::payloads().to_vec(),
+            os_version: defaults::os_version(),
         }
     }
 }
@@ -82,6 +192,7 @@ impl<'a> VersionQuery<'a> {
 
     fn matches(&self, version: &Version) -> bool {
         match (self, version) {
+            (VersionQuery::Match(exact), version) => version.to_string() == *exact,
             (VersionQuery::Semantic(mask), Version::Semantic(v)) => mask.matches(v),
             _ => false,
         }
@@ -97,6 +208,8 @@ pub(crate) struct ReleaseQueryIter<'a> {
     query: &'a ReleaseQuery<'a>,
     descriptor: &'a pahkat_types::package::Descriptor,
     next_release: usize,
+    /// Why the newest release with a target for this platform was skipped.
+    unmet: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -130,6 +243,14 @@ impl<'a> ReleaseQueryIter<'a> {
                 continue;
             }
 
+            if !self.query.versions.is_empty()
+                && !self.query.versions.iter().any(|x| x.matches(&release.version))
+            {
+                log::trace!("Skipping (version does not match)");
+                self.next_release += 1;
+                continue;
+            }
+
             if let Some(payload) = self.next_payload(release) {
                 log::trace!("Target resolved: {:?}", &payload.target);
                 self.next_release += 1;
@@ -143,36 +264,56 @@ impl<'a> ReleaseQueryIter<'a> {
         None
     }
 
+    /// The target of `release` best suiting the query, preferring native code over code for
+    /// a compatible architecture.
     #[inline(always)]
     fn next_payload(&mut self, release: &'a Release) -> Option<ReleaseQueryResponse<'a>> {
-        for ref target in release.target.iter() {
+        let mut best: Option<(usize, &'a Target)> = None;
+
+        for target in release.target.iter() {
             log::trace!(
                 "Candidate target: platform:{} arch:{:?}",
                 &target.platform,
                 &target.arch
             );
 
-            if target.platform != self.query.platform {
-                log::trace!("Skipping (platform does not match)");
-                continue;
-            }
+            let rank = match self
+                .query
+                .target_rank(&target.platform, target.arch.as_ref())
+            {
+                Some(v) => v,
+                None => {
+                    log::trace!("Skipping (platform or arch does not match)");
+                    continue;
+                }
+            };
 
-            if let Some(arch) = self.query.arch {
-                if let Some(ref target_arch) = target.arch {
-                    if target_arch != arch {
-                        log::trace!("Skipping (arch does not match)");
-                        continue;
-                    }
+            if let Err(reason) = self.query.check_os_version(target) {
+                log::trace!("Skipping ({})", &reason);
+                if self.unmet.is_none() {
+                    self.unmet = Some(format!("release {} {}", &release.version, reason));
                 }
-            } else if target.arch.is_some() {
-                log::trace!("Skipping (no arch in query but arch in target)");
                 continue;
             }
 
-            return Some(ReleaseQueryResponse { release, target });
+            match best {
+                Some((best_rank, _)) if best_rank <= rank => {}
+                _ => best = Some((rank, target)),
+            }
         }
 
-        None
+        best.map(|(_, target)| ReleaseQueryResponse { release, target })
+    }
+}
+
+impl<'a> ReleaseQueryIter<'a> {
+    /// Why no further release matched, explaining which criteria were not met if a release
+    /// only failed on those.
+    pub(crate) fn error(&self) -> PayloadError {
+        match self.unmet.as_ref() {
+            Some(reason) => PayloadError::CriteriaUnmet(reason.to_string()),
+            None => PayloadError::NoPayloadFound,
+        }
     }
 }
 
@@ -193,6 +334,7 @@ impl<'a> ReleaseQuery<'a> {
             query: self,
             descriptor,
             next_release: 0,
+            unmet: None,
         }
     }
 
@@ -221,15 +363,13 @@ impl<'a> ReleaseQuery<'a> {
             platform: key
                 .query
                 .platform
-                .as_ref()
-                .map(|x| &**x)
-                .unwrap_or_else(|| defaults::platform()),
+                .clone()
+                .unwrap_or_else(|| Platform::from(defaults::platform())),
             arch: key
                 .query
                 .arch
-                .as_ref()
-                .map(|x| &**x)
-                .or_else(|| defaults::arch()),
+                .clone()
+                .or_else(|| defaults::arch().map(Arch::from)),
             channels,
             versions: key
                 .query
@@ -238,6 +378,7 @@ impl<'a> ReleaseQuery<'a> {
                 .map(|v| vec![VersionQuery::Match(&*v)])
                 .unwrap_or_else(|| vec![]),
             payloads: defaults::payloads().to_vec(),
+            os_version: defaults::os_version(),
         }
     }
 }
@@ -260,6 +401,8 @@ pub struct PackageQuery {
     pub keys: Option<Vec<PackageKey>>,
     pub tags: Option<Vec<String>>,
     pub channel: Option<String>,
+    /// BCP 47 language tags, most preferred first, used to pick display names.
+    pub languages: Option<Vec<String>>,
 }
 
 pub(crate) fn resolve_package_query<'a>(
@@ -272,6 +415,9 @@ pub(crate) fn resolve_package_query<'a>(
 
     use crate::fbs::DescriptorExt;
 
+    let languages = query.languages.as_deref().unwrap_or(&[]);
+    let cache_dir = store.config().read().unwrap().settings().repo_cache_dir();
+
     // Only supports tags right now
     if let Some(tags) = query.tags.as_ref() {
         log::debug!("In tags");
@@ -279,6 +425,7 @@ pub(crate) fn resolve_package_query<'a>(
             .values()
             .flat_map(|repo| {
                 let repo_url = repo.info().repository.url.clone();
+                let strings = strings::cached(&cache_dir, &repo_url, languages);
 
                 log::debug!("Repo: {:?}", repo_url);
 
@@ -318,6 +465,26 @@ pub(crate) fn resolve_package_query<'a>(
                                 tags: descriptor.package.tags.clone(),
                                 name: descriptor.name.clone(),
                                 description: descriptor.description.clone(),
+                                display_name: descriptor.name.lookup(languages).cloned(),
+                                display_description: descriptor
+                                    .description
+                                    .lookup(languages)
+                                    .cloned(),
+                                tag_labels: descriptor
+                                    .package
+                                    .tags
+                                    .iter()
+                                    .filter_map(|tag| {
+                                        let label = strings.tags.get(tag)?;
+                                        Some((tag.clone(), label.clone()))
+                                    })
+                                    .collect(),
+                                channel_label: x
+                                    .release
+                                    .channel
+                                    .as_ref()
+                                    .and_then(|channel| strings.channels.get(channel))
+                                    .cloned(),
                                 release: crate::transaction::ResolvedRelease::new(
                                     x.release.clone(),
                                     x.target.clone(),
@@ -330,11 +497,16 @@ pub(crate) fn resolve_package_query<'a>(
         let status = descriptors
             .iter()
             .fold(PackageStatus::UpToDate, |acc, cur| {
-                match (acc, cur.status) {
+                // A newer installed version does not need any action
+                let status = match cur.status {
+                    PackageStatus::NewerThanAvailable => PackageStatus::UpToDate,
+                    v => v,
+                };
+                match (acc, status) {
                     // If currently requires update, nothing trumps this state
                     (PackageStatus::RequiresUpdate, _) => acc,
                     // Only requires update trumps NotInstalled
-                    (PackageStatus::NotInstalled, PackageStatus::RequiresUpdate) => cur.status,
+                    (PackageStatus::NotInstalled, PackageStatus::RequiresUpdate) => status,
                     (PackageStatus::NotInstalled, PackageStatus::UpToDate) => {
                         PackageStatus::RequiresUpdate
                     }
@@ -343,7 +515,7 @@ pub(crate) fn resolve_package_query<'a>(
                     }
                     // Everything trumps UpToDate
                     (PackageStatus::UpToDate, v) => v,
-                    _ => cur.status,
+                    _ => status,
                 }
             });
         let size = descriptors
@@ -385,11 +557,11 @@ pub(crate) fn resolve_payload<'a>(
     log::trace!("Resolving payload: {}", &package_key);
     let descriptor = resolve_package(package_key, repos)?;
     log::trace!("Package found: {}", &package_key);
-    query
-        .iter(&descriptor)
-        .next()
-        .map(|x| (x.target.clone(), x.release.clone(), descriptor.clone()))
-        .ok_or(PayloadError::NoPayloadFound)
+    let mut releases = query.iter(&descriptor);
+    match releases.next() {
+        Some(x) => Ok((x.target.clone(), x.release.clone(), descriptor.clone())),
+        None => Err(releases.error()),
+    }
 }
 
 pub(crate) fn import<'a>(
@@ -418,6 +590,7 @@ pub(crate) fn download<'a>(
     package_key: &PackageKey,
     query: &ReleaseQuery<'a>,
     repos: &HashMap<RepoUrl, LoadedRepository>,
+    cancel: crate::CancellationToken,
 ) -> std::pin::Pin<
     Box<
         dyn futures::stream::Stream<Item = crate::package_store::DownloadEvent>
@@ -455,7 +628,7 @@ pub(crate) fn download<'a>(
 
     let output_path = crate::repo::download_dir(&*config, &url);
     let stream = async_stream::stream! {
-        match dm.download(&url, output_path).await {
+        match dm.download(&url, output_path, cancel).await {
             Ok(mut v) => {
                 while let Some(value) = v.next().await {
                     yield value;
@@ -510,8 +683,8 @@ pub(crate) fn all_statuses<'a>(
     let repos = repos.read().unwrap();
 
     if let Some(repo) = repos.get(repo_url) {
-        let packages = repo.packages();
-        let packages = match packages.packages() {
+        let index = repo.packages();
+        let packages = match index.packages() {
             Some(v) => v,
             None => {
                 log::error!("No packages map in fbs for {:?}!", &repo_url);
@@ -519,13 +692,48 @@ pub(crate) fn all_statuses<'a>(
             }
         };
 
-        for id in packages.keys() {
+        // Synthetic packages are reported by evaluating their verifiers
+        let synthetic_ids = index
+            .synthetics()
+            .map(|x| x.keys().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for id in packages.keys().chain(synthetic_ids.into_iter()) {
             let key =
                 PackageKey::new_unchecked(repo.info().repository.url.clone(), id.to_string(), None);
             let status = store.status(&key, target);
             log::trace!("Package: {:?}, status: {:?}", &id, &status);
             map.insert(id.to_string(), status);
         }
+
+        // Packages installed under an ID that now redirects elsewhere are reported until
+        // an upgrade migrates them to their new key.
+        let redirect_ids = index
+            .redirects()
+            .map(|x| x.keys().collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        for id in redirect_ids {
+            let key =
+                PackageKey::new_unchecked(repo.info().repository.url.clone(), id.to_string(), None);
+            let new_key = match resolve_redirects(&key, &*repos) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            match store.status(&new_key, target) {
+                Ok(PackageStatus::NotInstalled) => {}
+                _ => continue,
+            }
+
+            match store.status(&key, target) {
+                Ok(PackageStatus::NotInstalled) => {}
+                status => {
+                    log::trace!("Package: {:?}, status: {:?}", &id, &status);
+                    map.insert(id.to_string(), status);
+                }
+            }
+        }
     } else {
         log::warn!("Did not find repo {:?} in available repos", &repo_url);
         log::trace!("Repos available: {:?}", &*repos);
@@ -534,52 +742,289 @@ pub(crate) fn all_statuses<'a>(
     map
 }
 
+/// Resolves a dependency, given either as a package identifier or a package key, to the
+/// key of the package without query parameters.
+pub(crate) fn dependency_key<S: PackageStore + ?Sized>(
+    store: &S,
+    dependency: &str,
+) -> Option<PackageKey> {
+    let key = if !dependency.starts_with("https://") && !dependency.starts_with("http://") {
+        store.find_package_by_id(dependency).map(|x| x.0)?
+    } else {
+        PackageKey::try_from(dependency).ok()?
+    };
+
+    Some(key.without_query_params())
+}
+
+/// The target of the release of `key` matching its query, if it is in the repositories.
+fn release_target<S: PackageStore + ?Sized>(store: &S, key: &PackageKey) -> Option<Target> {
+    let repos = store.repos();
+    let repos = repos.read().unwrap();
+    let descriptor = resolve_package(key, &*repos).ok()?;
+    let query = ReleaseQuery::new(key, &*repos);
+    let response = query.iter(&descriptor).next()?;
+    Some(response.target.clone())
+}
+
+/// The dependencies of the release of `key` matching its query, if it is in the repositories.
+fn release_dependencies<S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+) -> Option<Vec<String>> {
+    let target = release_target(store, key)?;
+    Some(target.dependencies.keys().cloned().collect())
+}
+
+/// The packages whose release matching their query provides the virtual name `name`, ordered
+/// by key so the same provider is picked every time.
+fn find_providers(name: &str, repos: &HashMap<RepoUrl, LoadedRepository>) -> Vec<PackageKey> {
+    let mut providers = vec![];
+
+    for (repo_url, repo) in repos.iter() {
+        let packages = match repo.packages().packages() {
+            Some(v) => v,
+            None => continue,
+        };
+
+        for (_, pkg) in packages.iter() {
+            let descriptor = match Descriptor::try_from(&pkg) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+            let key =
+                PackageKey::new_unchecked(repo_url.clone(), descriptor.package.id.clone(), None);
+
+            let provides = ReleaseQuery::new(&key, repos)
+                .iter(&descriptor)
+                .next()
+                .map(|x| x.target.provides.iter().any(|x| x == name))
+                .unwrap_or(false);
+
+            if provides {
+                providers.push(key);
+            }
+        }
+    }
+
+    providers.sort_by_key(|x| x.to_string());
+    providers
+}
+
+/// The installed packages that depend on `key`, according to the repository data for the
+/// release of each package that is installed.
+pub(crate) fn installed_dependents<S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+    target: crate::package_store::InstallTarget,
+) -> Result<Vec<PackageKey>, PackageStatusError> {
+    let key = key.clone().without_query_params();
+    let repo_urls = {
+        let repos = store.repos();
+        let repos = repos.read().unwrap();
+        repos.keys().cloned().collect::<Vec<_>>()
+    };
+
+    let mut dependents = vec![];
+
+    for repo_url in repo_urls.iter() {
+        for (id, status) in store.all_statuses(repo_url, target) {
+            match status {
+                Ok(PackageStatus::NotInstalled) | Err(_) => continue,
+                Ok(_) => {}
+            }
+
+            let mut candidate = PackageKey::new_unchecked(repo_url.clone(), id, None);
+            if candidate == key {
+                continue;
+            }
+            candidate.query.version = store.installed_version(&candidate, target)?;
+
+            let dependencies = match release_dependencies(store, &candidate) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            if dependencies
+                .iter()
+                .any(|x| dependency_key(store, x).as_ref() == Some(&key))
+            {
+                dependents.push(candidate.without_query_params());
+            }
+        }
+    }
+
+    Ok(dependents)
+}
+
+/// Every installed package, for any target, with a newer release available.
+pub(crate) fn updates<S: PackageStore + ?Sized>(
+    store: &S,
+) -> Result<Vec<crate::package_store::PackageUpdate>, PackageStatusError> {
+    use crate::package_store::PackageUpdate;
+    use pahkat_types::payload::Payload;
+
+    let repo_urls = {
+        let repos = store.repos();
+        let repos = repos.read().unwrap();
+        repos.keys().cloned().collect::<Vec<_>>()
+    };
+
+    let mut updates = vec![];
+
+    for repo_url in repo_urls.iter() {
+        for target in store.install_targets().iter() {
+            for (id, status) in store.all_statuses(repo_url, *target) {
+                match status {
+                    Ok(PackageStatus::RequiresUpdate) => {}
+                    Ok(_) => continue,
+                    Err(e) => {
+                        log::warn!("Could not get status of {} in {}: {:?}", &id, &repo_url, e);
+                        continue;
+                    }
+                }
+
+                let key = PackageKey::new_unchecked(repo_url.clone(), id, None);
+                // Synthetic packages are updated outside of pahkat
+                if crate::synth::is_synthetic(store, &key) {
+                    continue;
+                }
+
+                let installed_version = match store.installed_version(&key, *target)? {
+                    Some(v) => v,
+                    None => continue,
+                };
+
+                let (payload_target, release) = {
+                    let repos = store.repos();
+                    let repos = repos.read().unwrap();
+                    let query = ReleaseQuery::new(&key, &*repos);
+                    let (payload_target, release, _) = resolve_payload(&key, &query, &*repos)
+                        .map_err(PackageStatusError::Payload)?;
+                    (payload_target, release)
+                };
+
+                let is_reboot_required = match &payload_target.payload {
+                    Payload::MacOSPackage(pkg) => pkg
+                        .requires_reboot
+                        .contains(&pahkat_types::payload::macos::RebootSpec::Update),
+                    Payload::WindowsExecutable(pkg) => pkg
+                        .requires_reboot
+                        .contains(&pahkat_types::payload::windows::RebootSpec::Update),
+                    _ => false,
+                };
+
+                updates.push(PackageUpdate {
+                    key,
+                    target: *target,
+                    installed_version,
+                    candidate_version: release.version.to_string(),
+                    download_size: payload_target.payload.size(),
+                    is_reboot_required,
+                    channel: release.channel,
+                });
+            }
+        }
+    }
+
+    Ok(updates)
+}
+
 pub(crate) async fn strings<'p>(
     repo_urls: Vec<RepoUrl>,
     language: String,
+    cache_dir: std::path::PathBuf,
 ) -> HashMap<RepoUrl, crate::package_store::LocalizedStrings> {
     let futures = repo_urls
         .into_iter()
         .map(|url| {
-            let strings_url = url
-                .join("strings/")
-                .unwrap()
-                .join(&format!("{}.toml", language))
-                .unwrap();
-            (url, strings_url)
-        })
-        .map(|(url, strings_url)| async move {
-            let (tx, rx) = tokio::sync::oneshot::channel();
-            tokio::spawn(async move {
-                let response = match reqwest::get(strings_url).await {
-                    Ok(v) => match v.text().await {
-                        Ok(v) => match toml::from_str(&v) {
-                            Ok(v) => Some(v),
-                            Err(_) => None,
-                        },
-                        Err(_) => None,
-                    },
-                    Err(_) => None,
-                };
-                tx.send(response).unwrap();
-            });
-            let result = rx.await.unwrap();
-
-            (url, result)
+            let language = language.clone();
+            let cache_dir = cache_dir.clone();
+            async move {
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                let repo_url = url.clone();
+                tokio::spawn(async move {
+                    let response = strings::fetch_merged(repo_url, language, cache_dir).await;
+                    tx.send(response).unwrap();
+                });
+                let result = rx.await.unwrap();
+
+                (url, result)
+            }
         })
         .collect::<Vec<_>>();
     let results = futures::future::join_all(futures).await;
 
     results
         .into_iter()
-        .filter_map(|(k, v)| v.map(|v| (k, v)))
+        .filter_map(|(k, v)| v.map(|v| (k, v.into())))
         .collect::<HashMap<_, _>>()
 }
 
+/// The most redirects followed when resolving a package key.
+const MAX_REDIRECTS: usize = 8;
+
+/// The key a redirect in the index of `key`'s repository points to, if there is one.
+fn find_redirect(
+    key: &PackageKey,
+    repos: &HashMap<RepoUrl, LoadedRepository>,
+) -> Option<Result<PackageKey, String>> {
+    let repo = repos.get(&key.repository_url)?;
+    let index = repo.packages();
+    let redirect = index.redirects()?.get(&key.id)?;
+    let url = match redirect.url() {
+        Ok(v) => v,
+        Err(e) => return Some(Err(e.to_string())),
+    };
+
+    Some(PackageKey::try_from(url).map_err(|_| url.to_string()))
+}
+
+/// Follows the redirects of packages that were renamed or moved, returning the key the
+/// package has now. Query parameters of `key` are kept.
+///
+/// Returns `None` on a redirect loop, a chain longer than `MAX_REDIRECTS`, or a redirect
+/// that is not a valid package key.
+pub(crate) fn resolve_redirects(
+    key: &PackageKey,
+    repos: &HashMap<RepoUrl, LoadedRepository>,
+) -> Option<PackageKey> {
+    let mut visited = vec![key.clone().without_query_params()];
+    let mut current = key.clone();
+
+    loop {
+        let target = match find_redirect(&current, repos) {
+            None => return Some(current),
+            Some(Ok(v)) => v.without_query_params(),
+            Some(Err(url)) => {
+                log::error!("Invalid redirect from {} to {}", &current, url);
+                return None;
+            }
+        };
+
+        log::trace!("Following redirect from {} to {}", &current, &target);
+
+        if visited.contains(&target) {
+            log::error!("Redirect loop for {} at {}", &key, &target);
+            return None;
+        }
+
+        if visited.len() > MAX_REDIRECTS {
+            log::error!("Too many redirects for {}", &key);
+            return None;
+        }
+
+        visited.push(target.clone());
+        current = target;
+        current.query = key.query.clone();
+    }
+}
+
 pub(crate) fn find_package_by_key<'p>(
     package_key: &PackageKey,
     repos: &'p HashMap<RepoUrl, LoadedRepository>,
 ) -> Option<Package> {
+    let package_key = &resolve_redirects(package_key, repos)?;
     log::trace!("Resolving package: {}", &package_key);
     log::trace!(
         "Available repos: {:?}",
@@ -589,8 +1034,8 @@ pub(crate) fn find_package_by_key<'p>(
     repos.get(&package_key.repository_url).and_then(|r| {
         log::trace!("Got repo: {}", &r.info.repository.url);
         // TODO: need to check that any release supports the requested channel
-        let packages = r.packages();
-        let packages = match packages.packages() {
+        let index = r.packages();
+        let packages = match index.packages() {
             Some(v) => v,
             None => {
                 log::error!(
@@ -603,7 +1048,11 @@ pub(crate) fn find_package_by_key<'p>(
 
         let pkg = match packages.get(&package_key.id) {
             Some(x) => x,
-            None => return None,
+            None => {
+                let pkg = index.synthetics()?.get(&package_key.id)?;
+                log::trace!("Found synthetic pkg: {}", &package_key);
+                return (&pkg).try_into().map(Package::Synthetic).ok();
+            }
         };
         log::trace!("Found pkg: {}", &package_key);
 
@@ -622,8 +1071,8 @@ pub(crate) fn find_package_by_id(
     };
 
     repos.iter().find_map(|(key, repo)| {
-        let packages = repo.packages();
-        let packages = match packages.packages() {
+        let index = repo.packages();
+        let packages = match index.packages() {
             Some(v) => v,
             None => {
                 log::error!("No packages map in fbs for {:?}!", &key);
@@ -631,15 +1080,23 @@ pub(crate) fn find_package_by_id(
             }
         };
 
-        packages.get(package_id).map(|x| {
-            let key = PackageKey::new_unchecked(
-                repo.info().repository.url.clone(),
-                package_id.to_string(),
-                None,
-            );
+        let key = PackageKey::new_unchecked(
+            repo.info().repository.url.clone(),
+            package_id.to_string(),
+            None,
+        );
+
+        if let Some(x) = packages.get(package_id) {
+            return (&x).try_into().map(|p| (key, Package::Concrete(p))).ok();
+        }
+
+        if let Some(x) = index.synthetics().and_then(|x| x.get(package_id)) {
+            return (&x).try_into().map(|p| (key, Package::Synthetic(p))).ok();
+        }
 
-            (&x).try_into().map(|p| (key, Package::Concrete(p))).ok()
-        })?
+        index.redirects()?.get(package_id)?;
+        let key = resolve_redirects(&key, repos)?;
+        find_package_by_key(&key, repos).map(|p| (key, p))
     })
 }
 
@@ -649,6 +1106,7 @@ pub(crate) async fn refresh_repos(
 ) -> (
     HashMap<RepoUrl, LoadedRepository>,
     HashMap<RepoUrl, RepoDownloadError>,
+    Vec<RepoMove>,
 ) {
     let config = Arc::new(config);
 
@@ -693,11 +1151,25 @@ pub(crate) async fn refresh_repos(
 
     let mut res_map = HashMap::new();
     let mut err_map = HashMap::new();
+    let mut moves = vec![];
 
     for (key, value) in repo_data.into_iter() {
         match value {
             Ok(v) => {
                 log::debug!("Resolved repository: {:?}", &key);
+                // Moved repositories are keyed by their new URL, which their packages use
+                let key = match v.meta().moved_from.clone() {
+                    Some(from) => {
+                        let to = v.info().repository.url.clone();
+                        log::info!("Repository {} has moved to {}", &from, &to);
+                        moves.push(RepoMove {
+                            from,
+                            to: to.clone(),
+                        });
+                        to
+                    }
+                    None => key,
+                };
                 res_map.insert(key, v);
             }
             Err(e) => {
@@ -707,7 +1179,36 @@ pub(crate) async fn refresh_repos(
         }
     }
 
-    (res_map, err_map)
+    (res_map, err_map, moves)
+}
+
+/// Points an interrupted transaction at the new URLs of moved repositories, and replaces
+/// them in the config, if the settings allow it.
+pub(crate) fn record_moves(config: &Arc<RwLock<Config>>, moves: &[RepoMove]) {
+    if moves.is_empty() {
+        return;
+    }
+
+    use crate::transaction::journal::TransactionJournal;
+
+    let mut config = config.write().unwrap();
+
+    // Packages are keyed by the new URL whether or not the config is rewritten
+    for m in moves.iter() {
+        if let Err(e) = TransactionJournal::move_repo(&config, &m.from, &m.to) {
+            log::error!("Could not move journal of {} to {}: {}", &m.from, &m.to, e);
+        }
+    }
+
+    if !config.settings().rewrite_moved_repos() {
+        return;
+    }
+
+    for m in moves.iter() {
+        if let Err(e) = config.repos_mut().replace(&m.from, m.to.clone()) {
+            log::error!("Could not record move of {} to {}: {}", &m.from, &m.to, e);
+        }
+    }
 }
 
 pub(crate) fn clear_cache(config: &Arc<RwLock<Config>>) {
@@ -736,8 +1237,23 @@ pub enum PackageCandidateError {
     #[error("Could not resolve identifier to package key: `{0}`")]
     UnresolvedId(String),
 
-    #[error("Attempting to uninstall package required by installation set: `{0}`")]
-    UninstallConflict(PackageKey),
+    #[error("Attempting to uninstall package `{0}` required by `{1}` in the installation set")]
+    UninstallConflict(PackageKey, PackageKey),
+
+    #[error("Uninstalling `{0}` contradicts installing `{1}` in the same transaction")]
+    ActionContradiction(PackageKey, PackageKey),
+
+    #[error("Package `{0}` required by `{1}` must be installed outside of pahkat")]
+    SyntheticNotInstalled(PackageKey, PackageKey),
+
+    #[error("Package `{0}` conflicts with `{1}`")]
+    Conflict(PackageKey, PackageKey),
+
+    #[error("Could not uninstall package `{0}`, which `{1}` replaces")]
+    Replace(PackageKey, PackageKey, #[source] Box<PackageCandidateError>),
+
+    #[error("An interrupted transaction must be resumed, rolled back or discarded first")]
+    InterruptedTransaction,
 }
 
 use crate::{package_store::InstallTarget, PackageActionType};
@@ -776,7 +1292,9 @@ fn resolve_package_candidate(
                     use pahkat_types::payload::macos::RebootSpec;
                     match status {
                         PackageStatus::NotInstalled => pkg.requires_reboot.contains(&RebootSpec::Install),
-                        PackageStatus::RequiresUpdate => pkg.requires_reboot.contains(&RebootSpec::Update),
+                        PackageStatus::RequiresUpdate | PackageStatus::NewerThanAvailable => {
+                            pkg.requires_reboot.contains(&RebootSpec::Update)
+                        }
                         _ => false,
                     }
                 }
@@ -784,7 +1302,9 @@ fn resolve_package_candidate(
                     use pahkat_types::payload::windows::RebootSpec;
                     match status {
                         PackageStatus::NotInstalled => pkg.requires_reboot.contains(&RebootSpec::Install),
-                        PackageStatus::RequiresUpdate => pkg.requires_reboot.contains(&RebootSpec::Update),
+                        PackageStatus::RequiresUpdate | PackageStatus::NewerThanAvailable => {
+                            pkg.requires_reboot.contains(&RebootSpec::Update)
+                        }
                         _ => false,
                     }
                 }
@@ -863,28 +1383,265 @@ fn recurse_package_set(
         .dependencies
         .keys()
         .try_fold((), |_, key| {
+            // FIXME: this uninstall thing here is a workaround to make uninstall work at all.
+            // No dependency cleanup will occur.
+            if package_candidate.action == PackageActionType::Uninstall {
+                return Ok(());
+            }
+
             let key = if !key.starts_with("https://") && !key.starts_with("http://") {
-                store
-                    .find_package_by_id(key)
-                    .map(|x| x.0)
-                    .ok_or_else(|| PackageCandidateError::UnresolvedId(key.to_string()))?
+                match store.find_package_by_id(key) {
+                    Some(x) => x.0,
+                    // Not a package, so it may be a virtual name another package provides
+                    None => match resolve_provider(store, key, install_target, repos, set)? {
+                        Some(v) => v,
+                        None => return Ok(()),
+                    },
+                }
             } else {
                 PackageKey::try_from(&**key)
                     .map_err(|_| PackageCandidateError::UnresolvedId(key.to_string()))?
             };
 
-            // FIXME: this uninstall thing here is a workaround to make uninstall work at all.
-            // No dependency cleanup will occur.
-            if set.contains_key(&key) || package_candidate.action == PackageActionType::Uninstall {
+            // Synthetic packages cannot be installed, only detected
+            if crate::synth::is_synthetic(store, &key) {
+                if is_installed(store, &key, install_target) {
+                    return Ok(());
+                }
+
+                return Err(PackageCandidateError::SyntheticNotInstalled(
+                    key,
+                    package_candidate.package_key.to_owned(),
+                ));
+            }
+
+            if let Some(existing) = find_in_set(set, &key.clone().without_query_params()) {
+                // A dependency of a package being installed cannot be removed by the same set
+                if existing.action == PackageActionType::Uninstall {
+                    return Err(PackageCandidateError::UninstallConflict(
+                        key,
+                        package_candidate.package_key.to_owned(),
+                    ));
+                }
                 return Ok(());
             }
 
             let candidate = resolve_package_candidate(store, &(PackageActionType::Install, key.to_owned()), install_target, repos)?;
-            set.insert(key, candidate);
-            Ok(())
+            set.insert(key, candidate.clone());
+
+            // Dependencies of dependencies are needed too
+            recurse_package_set(store, &candidate, install_target, repos, set)
         })
 }
 
+fn find_in_set<'s>(
+    set: &'s HashMap<PackageKey, PackageCandidate>,
+    key: &PackageKey,
+) -> Option<&'s PackageCandidate> {
+    set.values()
+        .find(|x| &x.package_key.clone().without_query_params() == key)
+}
+
+fn is_installed(
+    store: &dyn PackageStore,
+    key: &PackageKey,
+    install_target: &[InstallTarget],
+) -> bool {
+    install_target
+        .iter()
+        .any(|target| match store.status(key, *target) {
+            Ok(PackageStatus::NotInstalled) | Err(_) => false,
+            Ok(_) => true,
+        })
+}
+
+/// Picks the package to install for a dependency on the virtual name `name`. Returns `None`
+/// if a package providing it is already installed or being installed.
+fn resolve_provider(
+    store: &dyn PackageStore,
+    name: &str,
+    install_target: &[InstallTarget],
+    repos: &HashMap<RepoUrl, LoadedRepository>,
+    set: &HashMap<PackageKey, PackageCandidate>,
+) -> Result<Option<PackageKey>, PackageCandidateError> {
+    let providers = find_providers(name, repos);
+
+    let is_satisfied = providers.iter().any(|key| match find_in_set(set, key) {
+        Some(candidate) => candidate.action == PackageActionType::Install,
+        None => is_installed(store, key, install_target),
+    });
+
+    if is_satisfied {
+        return Ok(None);
+    }
+
+    match providers.into_iter().next() {
+        Some(key) => {
+            log::debug!("Dependency on {} provided by {}", name, &key);
+            Ok(Some(key))
+        }
+        None => Err(PackageCandidateError::UnresolvedId(name.to_string())),
+    }
+}
+
+/// Adds the uninstallation of installed packages that the packages being installed replace.
+///
+/// Replaced packages are looked up in the store's records, as a retired package may no
+/// longer be in any repository. If one cannot be uninstalled, the transaction fails rather
+/// than leave both packages installed.
+fn add_replaced_packages(
+    store: &dyn PackageStore,
+    install_target: &[InstallTarget],
+    repos: &HashMap<RepoUrl, LoadedRepository>,
+    set: &mut HashMap<PackageKey, PackageCandidate>,
+) -> Result<(), PackageCandidateError> {
+    let installs = set
+        .values()
+        .filter(|x| x.action == PackageActionType::Install)
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let replacing = match installs.iter().find(|x| !x.target.replaces.is_empty()) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let mut installed = vec![];
+    for target in install_target.iter() {
+        let keys = store
+            .installed_packages(*target)
+            .map_err(|e| PackageCandidateError::Status(replacing.package_key.to_owned(), e))?;
+        for key in keys {
+            if !installed.contains(&key) {
+                installed.push(key);
+            }
+        }
+    }
+
+    for candidate in installs.iter() {
+        for replaced in candidate.target.replaces.iter() {
+            if let Some(key) = dependency_key(store, replaced) {
+                match find_in_set(set, &key) {
+                    Some(existing) if existing.action == PackageActionType::Install => {
+                        return Err(PackageCandidateError::Conflict(
+                            candidate.package_key.to_owned(),
+                            key,
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+
+            let replaced_url = PackageKey::try_from(&**replaced)
+                .ok()
+                .map(|x| x.without_query_params());
+            let matches = installed.iter().filter(|key| match &replaced_url {
+                Some(url) => *key == url,
+                None => &key.id == replaced,
+            });
+
+            for key in matches {
+                if find_in_set(set, key).is_some() {
+                    continue;
+                }
+
+                let uninstall = (PackageActionType::Uninstall, key.clone());
+                let v = resolve_package_candidate(store, &uninstall, install_target, repos)
+                    .map_err(|e| {
+                        PackageCandidateError::Replace(
+                            key.to_owned(),
+                            candidate.package_key.to_owned(),
+                            Box::new(e),
+                        )
+                    })?;
+                log::debug!("{} replaces {}", &candidate.package_key, key);
+                set.insert(key.to_owned(), v);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether the package `other` cannot be installed alongside `candidate`, in either direction.
+fn conflicts_with(
+    store: &dyn PackageStore,
+    candidate: &PackageCandidate,
+    other: &PackageKey,
+) -> bool {
+    let key = candidate.package_key.clone().without_query_params();
+    if &key == other {
+        return false;
+    }
+
+    if candidate
+        .target
+        .conflicts
+        .iter()
+        .any(|x| dependency_key(store, x).as_ref() == Some(other))
+    {
+        return true;
+    }
+
+    match release_target(store, other) {
+        Some(target) => target
+            .conflicts
+            .iter()
+            .any(|x| dependency_key(store, x).as_ref() == Some(&key)),
+        None => false,
+    }
+}
+
+/// Refuses a set that would leave conflicting packages installed together, whether both are
+/// being installed or one of them is already installed and is not being uninstalled.
+fn check_conflicts(
+    store: &dyn PackageStore,
+    install_target: &[InstallTarget],
+    set: &HashMap<PackageKey, PackageCandidate>,
+) -> Result<(), PackageCandidateError> {
+    let installs = set
+        .values()
+        .filter(|x| x.action == PackageActionType::Install)
+        .collect::<Vec<_>>();
+
+    for candidate in installs.iter() {
+        for other in installs.iter() {
+            let other = other.package_key.clone().without_query_params();
+            if conflicts_with(store, candidate, &other) {
+                return Err(PackageCandidateError::Conflict(
+                    candidate.package_key.to_owned(),
+                    other,
+                ));
+            }
+        }
+    }
+
+    for target in install_target.iter() {
+        let installed = match store.installed_packages(*target) {
+            Ok(v) => v,
+            Err(e) => {
+                log::warn!("Could not list installed packages: {:?}", e);
+                continue;
+            }
+        };
+
+        for key in installed {
+            if find_in_set(set, &key).is_some() {
+                continue;
+            }
+
+            if let Some(candidate) = installs.iter().find(|x| conflicts_with(store, x, &key)) {
+                return Err(PackageCandidateError::Conflict(
+                    candidate.package_key.to_owned(),
+                    key,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub(crate) fn resolve_package_set(
     store: &dyn PackageStore,
     candidates: &[(PackageActionType, PackageKey)],
@@ -893,6 +1650,25 @@ pub(crate) fn resolve_package_set(
     let repos = store.repos();
     let repos = repos.read().unwrap();
 
+    // The same package cannot be requested with different actions
+    for (i, (action, key)) in candidates.iter().enumerate() {
+        let id = key.clone().without_query_params();
+        let other = candidates[..i]
+            .iter()
+            .find(|(a, k)| a != action && k.clone().without_query_params() == id);
+
+        if let Some((_, other)) = other {
+            let (uninstall, install) = match action {
+                PackageActionType::Uninstall => (key, other),
+                PackageActionType::Install => (other, key),
+            };
+            return Err(PackageCandidateError::ActionContradiction(
+                uninstall.to_owned(),
+                install.to_owned(),
+            ));
+        }
+    }
+
     // Resolve initial package set
     let mut candidate_set = candidates
         .iter()
@@ -915,17 +1691,161 @@ pub(crate) fn resolve_package_set(
         )
     })?;
 
+    add_replaced_packages(store, install_target, &*repos, &mut candidate_set)?;
+    check_conflicts(store, install_target, &candidate_set)?;
+
     // Take our candidate set and resolve it down to a mutation set
-    Ok(candidate_set
+    let mutation_set = candidate_set
         .into_iter()
         .filter_map(|(key, candidate)| {
             if candidate.action == PackageActionType::Install && candidate.status == PackageStatus::UpToDate {
                 None
+            } else if candidate.action == PackageActionType::Install
+                && candidate.status == PackageStatus::NewerThanAvailable
+                && key.query.version.is_none()
+            {
+                // Only downgrade when a specific older release was asked for
+                None
             } else if candidate.action == PackageActionType::Uninstall && candidate.status == PackageStatus::NotInstalled {
                 None
             } else {
                 Some(candidate)
             }
         })
-        .collect())
+        .collect::<Vec<_>>();
+
+    Ok(order_by_dependencies(store, mutation_set))
+}
+
+/// Orders `set` with uninstalls first, so packages being replaced are gone before what
+/// replaces them, followed by installs with each package after its dependencies.
+fn order_by_dependencies(
+    store: &dyn PackageStore,
+    set: Vec<PackageCandidate>,
+) -> Vec<PackageCandidate> {
+    fn visit(
+        store: &dyn PackageStore,
+        index: usize,
+        installs: &[PackageCandidate],
+        visited: &mut [bool],
+        order: &mut Vec<usize>,
+    ) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+
+        for dependency in installs[index].target.dependencies.keys() {
+            let key = match dependency_key(store, dependency) {
+                Some(v) => v,
+                None => continue,
+            };
+            let position = installs
+                .iter()
+                .position(|x| x.package_key.clone().without_query_params() == key);
+            if let Some(position) = position {
+                visit(store, position, installs, visited, order);
+            }
+        }
+
+        order.push(index);
+    }
+
+    let (mut ordered, installs): (Vec<_>, Vec<_>) = set
+        .into_iter()
+        .partition(|x| x.action == PackageActionType::Uninstall);
+
+    let mut visited = vec![false; installs.len()];
+    let mut order = vec![];
+    for index in 0..installs.len() {
+        visit(store, index, &installs, &mut visited, &mut order);
+    }
+
+    let mut installs = installs.into_iter().map(Some).collect::<Vec<_>>();
+    ordered.extend(order.into_iter().filter_map(|i| installs[i].take()));
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pahkat_types::payload::{tarball, Payload};
+
+    fn target(min: Option<&str>, max: Option<&str>) -> Target {
+        Target::builder()
+            .platform(Platform::Linux)
+            .min_os_version(min.map(str::to_string))
+            .max_os_version(max.map(str::to_string))
+            .payload(Payload::TarballPackage(
+                tarball::Package::builder()
+                    .url(Url::parse("https://example.com/thing.tar.xz").unwrap())
+                    .size(1000)
+                    .installed_size(100000)
+                    .build(),
+            ))
+            .build()
+    }
+
+    fn query(os_version: Option<&str>) -> ReleaseQuery<'_> {
+        ReleaseQuery {
+            os_version,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn os_version_parts_ignore_suffixes() {
+        assert_eq!(os_version_parts("10.15.7"), vec![10, 15, 7]);
+        assert_eq!(os_version_parts("5.4.0-42-generic"), vec![5, 4, 0]);
+        assert_eq!(os_version_parts("22.04"), vec![22, 4]);
+        assert_eq!(os_version_parts(" 11 "), vec![11]);
+        assert_eq!(os_version_parts("10.0rc1.3"), vec![10, 0]);
+        assert!(os_version_parts("rolling").is_empty());
+    }
+
+    #[test]
+    fn os_versions_compare_as_far_as_the_bound() {
+        let cmp = |a, b| cmp_os_version(&os_version_parts(a), &os_version_parts(b));
+        assert_eq!(cmp("10.15.7", "10.15"), Ordering::Equal);
+        assert_eq!(cmp("10.15", "10.15.7"), Ordering::Less);
+        assert_eq!(cmp("11", "10.15"), Ordering::Greater);
+        assert_eq!(cmp("10.9", "10.15"), Ordering::Less);
+        assert_eq!(cmp("5.4.0-42-generic", "5.4"), Ordering::Equal);
+    }
+
+    #[test]
+    fn os_version_bounds() {
+        let macos = target(Some("10.13"), Some("10.15"));
+        let check = |version| query(Some(version)).check_os_version(&macos);
+        assert!(check("10.13").is_ok());
+        assert!(check("10.15.7").is_ok());
+        assert_eq!(
+            check("10.12.6"),
+            Err("requires OS version 10.13 or newer, but this is 10.12.6".to_string())
+        );
+        assert_eq!(
+            check("11.0.1"),
+            Err("requires OS version 10.15 or older, but this is 11.0.1".to_string())
+        );
+
+        let linux = target(Some("5.4"), None);
+        let check = |version| query(Some(version)).check_os_version(&linux);
+        assert!(check("5.4.0-42-generic").is_ok());
+        assert!(check("4.19.0-1-amd64").is_err());
+    }
+
+    #[test]
+    fn unknown_os_version() {
+        // Without bounds, the OS version does not matter
+        assert!(query(None).check_os_version(&target(None, None)).is_ok());
+
+        let reason = query(None)
+            .check_os_version(&target(Some("10.13"), None))
+            .unwrap_err();
+        assert_eq!(
+            PayloadError::CriteriaUnmet(reason).to_string(),
+            "No payload meets the criteria: the OS version could not be detected"
+        );
+    }
 }
@@ -3,7 +3,13 @@ use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 use crate::pahkat_fbs;
-use pahkat_types::{repo::RepoUrl, PackageKey};
+use pahkat_types::{
+    repo::{RepoUrl, Repository},
+    PackageKey,
+};
+
+/// How many redirects are followed when loading a repository index.
+const MAX_REDIRECTS: usize = 4;
 
 #[derive(Debug, thiserror::Error)]
 pub enum RepoDownloadError {
@@ -15,11 +21,30 @@ pub enum RepoDownloadError {
 
     #[error("I/O error")]
     IoError(#[from] std::io::Error),
+
+    #[error("Repository {0} redirected to {1}, which does not accept the redirection")]
+    RedirectNotAccepted(RepoUrl, RepoUrl),
+
+    #[error("Repository {0} redirected too many times")]
+    TooManyRedirects(RepoUrl),
+
+    #[error("Repository {0} is of a kind this client does not support")]
+    UnsupportedKind(RepoUrl),
+}
+
+/// The move of a repository to a new URL, announced by a redirect at its old URL.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RepoMove {
+    pub from: RepoUrl,
+    pub to: RepoUrl,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LoadedRepositoryMeta {
     pub channel: Option<String>,
+    /// The configured URL of the repository, if it redirected to where it was loaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<RepoUrl>,
     // pub hash_id: String,
     // TODO: last update
 }
@@ -57,13 +82,42 @@ impl LoadedRepository {
 
                 log::trace!("Loading repo: {} channel:{:?}", &url, &channel);
 
-                let info = client
-                    .get(&format!("{}/index.toml", url))
-                    .send()
-                    .await?
-                    .text()
-                    .await?;
-                let info: pahkat_types::repo::Index = toml::from_str(&info)?;
+                let origin = url;
+                let mut url = origin.clone();
+                let mut redirects = 0;
+
+                // A redirect is only followed if the index it leads to accepts redirections
+                // from the configured URL, so a stale or hijacked redirect cannot silently
+                // replace a repository.
+                let info = loop {
+                    let info = client
+                        .get(&format!("{}/index.toml", url))
+                        .send()
+                        .await?
+                        .text()
+                        .await?;
+
+                    match toml::from_str(&info)? {
+                        Repository::Index(info) => break info,
+                        Repository::Redirect(redirect) => {
+                            redirects += 1;
+                            if redirects > MAX_REDIRECTS {
+                                return Err(RepoDownloadError::TooManyRedirects(origin));
+                            }
+                            log::debug!("Repo {} redirects to {}", &url, &redirect.redirect.url);
+                            url = redirect.redirect.url;
+                        }
+                        _ => return Err(RepoDownloadError::UnsupportedKind(url)),
+                    }
+                };
+
+                let moved_from = if url == origin {
+                    None
+                } else if info.repository.accepted_redirections.contains(&origin) {
+                    Some(origin)
+                } else {
+                    return Err(RepoDownloadError::RedirectNotAccepted(origin, url));
+                };
 
                 let packages = client
                     .get(&format!("{}/packages/index.bin", url))
@@ -79,6 +133,7 @@ impl LoadedRepository {
                     packages,
                     meta: LoadedRepositoryMeta {
                         channel,
+                        moved_from,
                         // hash_id: "".into(),
                     },
                 };
@@ -0,0 +1,163 @@
+//! Translations of the tags and channels of repositories.
+//!
+//! Each `strings/<language>.toml` file of a repository is cached under the repository cache
+//! directory, so labels are still available offline and when resolving package queries,
+//! which do not touch the network.
+
+use std::path::{Path, PathBuf};
+
+use pahkat_types::lang_tag::{fallbacks, is_valid_lang_tag, FALLBACK_LANG_TAG};
+use pahkat_types::repo::{Localisation, RepoUrl};
+
+use crate::ext::PathExt;
+
+/// The cache file for `language`, or `None` if it is not a valid language tag. Languages
+/// come from clients, and anything else could point outside of the cache directory.
+fn cache_path(cache_dir: &Path, url: &RepoUrl, language: &str) -> Option<PathBuf> {
+    if !is_valid_lang_tag(language) {
+        return None;
+    }
+
+    let path = cache_dir
+        .join_sha256(url.as_str().as_bytes())
+        .join("strings")
+        .join(format!("{}.toml", language));
+    Some(path)
+}
+
+/// The languages to look up for `languages`, most specific first, ending with the
+/// fallback language. `se-NO` gives `se-NO`, `se` and `en`. Invalid language tags are
+/// skipped.
+fn chain<S: AsRef<str>>(languages: &[S]) -> Vec<String> {
+    let mut chain: Vec<String> = vec![];
+
+    let tags = languages
+        .iter()
+        .map(|x| x.as_ref())
+        .filter(|x| {
+            let is_valid = is_valid_lang_tag(x);
+            if !is_valid {
+                log::warn!("Ignoring invalid language tag: {:?}", x);
+            }
+            is_valid
+        })
+        .flat_map(fallbacks)
+        .chain(std::iter::once(FALLBACK_LANG_TAG));
+
+    for tag in tags {
+        if !chain.iter().any(|x| x.eq_ignore_ascii_case(tag)) {
+            chain.push(tag.to_string());
+        }
+    }
+
+    chain
+}
+
+fn load_cached(path: &Path) -> Option<Localisation> {
+    let file = std::fs::read_to_string(path).ok()?;
+
+    match toml::from_str(&file) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            log::error!("Could not parse cached strings {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn write_cache(path: &Path, data: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, data)
+}
+
+/// Downloads the strings of `url` for exactly `language`, updating the cache. The cached
+/// copy is used if the repository cannot be reached.
+async fn fetch(url: &RepoUrl, language: &str, cache_dir: &Path) -> Option<Localisation> {
+    let path = cache_path(cache_dir, url, language)?;
+    let strings_url = url
+        .join("strings/")
+        .and_then(|x| x.join(&format!("{}.toml", language)))
+        .ok()?;
+
+    let response = match reqwest::get(strings_url.clone()).await {
+        Ok(v) if v.status() == reqwest::StatusCode::NOT_FOUND => {
+            // The repository has no strings, or no longer has them, for this language
+            let _ = std::fs::remove_file(&path);
+            return None;
+        }
+        Ok(v) => v.error_for_status(),
+        Err(e) => Err(e),
+    };
+
+    let text = match response {
+        Ok(v) => v.text().await,
+        Err(e) => Err(e),
+    };
+
+    let text = match text {
+        Ok(v) => v,
+        Err(e) => {
+            log::warn!("Could not download {}, using cache: {}", &strings_url, e);
+            return load_cached(&path);
+        }
+    };
+
+    match toml::from_str(&text) {
+        Ok(strings) => {
+            if let Err(e) = write_cache(&path, &text) {
+                log::error!("Could not cache strings {:?}: {}", &path, e);
+            }
+            Some(strings)
+        }
+        Err(e) => {
+            log::error!("Could not parse {}, using cache: {}", &strings_url, e);
+            load_cached(&path)
+        }
+    }
+}
+
+/// Downloads the strings of `url` for `language` and each less specific language, with
+/// labels from the most specific language taking precedence.
+pub(crate) async fn fetch_merged(
+    url: RepoUrl,
+    language: String,
+    cache_dir: PathBuf,
+) -> Option<Localisation> {
+    if !is_valid_lang_tag(&language) {
+        log::warn!("Ignoring invalid language tag: {:?}", &language);
+        return None;
+    }
+
+    let mut result: Option<Localisation> = None;
+
+    for tag in chain(&[language]) {
+        if let Some(strings) = fetch(&url, &tag, &cache_dir).await {
+            match result.as_mut() {
+                Some(result) => result.merge_missing(strings),
+                None => result = Some(strings),
+            }
+        }
+    }
+
+    result
+}
+
+/// The cached strings of `url` for the preferred `languages`, most preferred first.
+pub(crate) fn cached<S: AsRef<str>>(
+    cache_dir: &Path,
+    url: &RepoUrl,
+    languages: &[S],
+) -> Localisation {
+    let mut result = Localisation::default();
+
+    for tag in chain(languages) {
+        let strings = cache_path(cache_dir, url, &tag).and_then(|x| load_cached(&x));
+        if let Some(strings) = strings {
+            result.merge_missing(strings);
+        }
+    }
+
+    result
+}
@@ -0,0 +1,302 @@
+//! Status of synthetic packages, which describe software installed outside of pahkat.
+//!
+//! Synthetic packages cannot be installed or uninstalled. Their status comes from evaluating
+//! the verifier of each release against the system, newest release first.
+
+use std::path::{Path, PathBuf};
+
+use pahkat_types::package::{Package, Version};
+use pahkat_types::synth::{file, Descriptor, Release, Target, Verifier};
+
+use crate::package_store::{InstallTarget, PackageStore};
+use crate::repo::{PayloadError, ReleaseQuery};
+use crate::transaction::{PackageStatus, PackageStatusError};
+use crate::PackageKey;
+
+/// What a verifier found on the system.
+enum Detected {
+    Missing,
+    /// The software is present, but its version is not known.
+    Present,
+    Version(String),
+}
+
+fn descriptor<S: PackageStore + ?Sized>(store: &S, key: &PackageKey) -> Option<Descriptor> {
+    match store.find_package_by_key(key)? {
+        Package::Synthetic(descriptor) => Some(descriptor),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_synthetic<S: PackageStore + ?Sized>(store: &S, key: &PackageKey) -> bool {
+    descriptor(store, key).is_some()
+}
+
+/// The status of `key`, or `None` if it is not a synthetic package.
+pub(crate) fn status<S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+    target: InstallTarget,
+) -> Option<Result<PackageStatus, PackageStatusError>> {
+    let descriptor = descriptor(store, key)?;
+    Some(status_impl(store, key, &descriptor, target))
+}
+
+/// The installed version of `key`, or `None` if it is not a synthetic package.
+pub(crate) fn installed_version<S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+    target: InstallTarget,
+) -> Option<Result<Option<String>, PackageStatusError>> {
+    let descriptor = descriptor(store, key)?;
+    let candidates = candidates(store, key, &descriptor);
+    Some(installed_version_impl(store, &candidates, target))
+}
+
+fn status_impl<S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+    descriptor: &Descriptor,
+    target: InstallTarget,
+) -> Result<PackageStatus, PackageStatusError> {
+    let candidates = candidates(store, key, descriptor);
+    let (release, _) = candidates
+        .first()
+        .ok_or(PackageStatusError::Payload(PayloadError::NoPayloadFound))?;
+    let candidate_version =
+        Version::new(&release.version).map_err(|_| PackageStatusError::ParsingVersion)?;
+
+    match installed_version_impl(store, &candidates, target)? {
+        Some(installed) => crate::cmp::cmp(&installed, &candidate_version),
+        None => Ok(PackageStatus::NotInstalled),
+    }
+}
+
+/// The releases of `descriptor` available to `key`, with their target for this platform.
+fn candidates<'d, S: PackageStore + ?Sized>(
+    store: &S,
+    key: &PackageKey,
+    descriptor: &'d Descriptor,
+) -> Vec<(&'d Release, &'d Target)> {
+    let repos = store.repos();
+    let repos = repos.read().unwrap();
+    let query = ReleaseQuery::new(key, &*repos);
+
+    descriptor
+        .releases
+        .iter()
+        .filter(|release| {
+            // An empty channel is the default channel
+            let channel = Some(&*release.channel).filter(|x| !x.is_empty());
+            query.accepts_channel(channel)
+        })
+        .filter_map(|release| {
+            release
+                .targets
+                .iter()
+                .filter_map(|target| {
+                    let rank = query.target_rank(&target.platform, target.arch.as_ref())?;
+                    Some((rank, target))
+                })
+                .min_by_key(|(rank, _)| *rank)
+                .map(|(_, target)| (release, target))
+        })
+        .collect()
+}
+
+/// Evaluates verifiers until one of them detects the package. A verifier that detects the
+/// package without a version means its release is the one installed.
+fn installed_version_impl<S: PackageStore + ?Sized>(
+    store: &S,
+    candidates: &[(&Release, &Target)],
+    target: InstallTarget,
+) -> Result<Option<String>, PackageStatusError> {
+    for (release, release_target) in candidates.iter() {
+        match detect(store, &release_target.verifier, target)? {
+            Detected::Missing => continue,
+            Detected::Present => return Ok(Some(release.version.clone())),
+            Detected::Version(version) => return Ok(Some(version)),
+        }
+    }
+
+    Ok(None)
+}
+
+#[cfg_attr(not(any(windows, target_os = "macos")), allow(unused_variables))]
+fn detect<S: PackageStore + ?Sized>(
+    store: &S,
+    verifier: &Verifier,
+    target: InstallTarget,
+) -> Result<Detected, PackageStatusError> {
+    match verifier {
+        Verifier::FileRef(v) => Ok(detect_file(store, v)),
+        #[cfg(windows)]
+        Verifier::WindowsRegistryKey(v) => Ok(detect_registry_key(v, target)),
+        #[cfg(all(target_os = "macos", feature = "macos"))]
+        Verifier::MacOSPackageRef(v) => Ok(detect_macos_package(v, target)),
+        #[cfg(target_os = "macos")]
+        Verifier::MacOSPathRef(v) => Ok(detect_macos_path(v)),
+        _ => Err(PackageStatusError::UnsupportedVerifier),
+    }
+}
+
+/// Resolves a path from a verifier, relative paths being relative to the store's install
+/// directory.
+fn resolve_path<S: PackageStore + ?Sized>(store: &S, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Some(path.to_path_buf());
+    }
+
+    match store.install_dir() {
+        Some(dir) => Some(dir.join(path)),
+        None => {
+            log::warn!(
+                "Relative path {:?} in store without install directory",
+                path
+            );
+            None
+        }
+    }
+}
+
+fn detect_file<S: PackageStore + ?Sized>(store: &S, verifier: &file::FileRef) -> Detected {
+    match resolve_path(store, &verifier.path) {
+        Some(path) if path.exists() => {}
+        _ => return Detected::Missing,
+    }
+
+    let version_file = match verifier.version_file.as_ref() {
+        Some(v) => v,
+        None => return Detected::Present,
+    };
+
+    let version_file = match resolve_path(store, version_file) {
+        Some(v) => v,
+        None => return Detected::Missing,
+    };
+
+    match std::fs::read_to_string(&version_file) {
+        Ok(version) if version.trim().is_empty() => Detected::Present,
+        Ok(version) => Detected::Version(version.trim().to_string()),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::error!("Could not read version file {:?}: {}", &version_file, e);
+            }
+            Detected::Missing
+        }
+    }
+}
+
+#[cfg(windows)]
+fn detect_registry_key(
+    verifier: &pahkat_types::synth::windows::RegistryKey,
+    target: InstallTarget,
+) -> Detected {
+    use winreg::enums::*;
+    use winreg::RegKey;
+
+    let root = match target {
+        InstallTarget::System => RegKey::predef(HKEY_LOCAL_MACHINE),
+        InstallTarget::User => RegKey::predef(HKEY_CURRENT_USER),
+    };
+
+    let key = match root.open_subkey(&verifier.path) {
+        Ok(v) => v,
+        Err(_) => match root.open_subkey_with_flags(&verifier.path, KEY_READ | KEY_WOW64_64KEY) {
+            Ok(v) => v,
+            Err(_) => return Detected::Missing,
+        },
+    };
+
+    match key.get_value::<String, _>(&verifier.name) {
+        Ok(version) => Detected::Version(version),
+        Err(_) => Detected::Missing,
+    }
+}
+
+/// Whether `value` lies within the inclusive bounds, where they are given.
+#[cfg(target_os = "macos")]
+fn within(value: &str, min: Option<&String>, max: Option<&String>) -> bool {
+    let value = match Version::new(value) {
+        Ok(v) => v,
+        Err(_) => return min.is_none() && max.is_none(),
+    };
+
+    let min = min.map(|x| Version::new(x).map(|min| value >= min).unwrap_or(false));
+    let max = max.map(|x| Version::new(x).map(|max| value <= max).unwrap_or(false));
+
+    min.unwrap_or(true) && max.unwrap_or(true)
+}
+
+/// Package receipts do not record a build number, so only the version bounds apply.
+#[cfg(all(target_os = "macos", feature = "macos"))]
+fn detect_macos_package(
+    verifier: &pahkat_types::synth::macos::PackageRef,
+    target: InstallTarget,
+) -> Detected {
+    use crate::transaction::install::ProcessError;
+
+    match crate::package_store::macos::get_package_info(&verifier.pkg_id, target) {
+        Ok(info) => {
+            let min = verifier.min_version.as_ref();
+            let max = verifier.max_version.as_ref();
+            if within(&info.pkg_version, min, max) {
+                Detected::Version(info.pkg_version)
+            } else {
+                Detected::Missing
+            }
+        }
+        Err(ProcessError::NotFound) => Detected::Missing,
+        Err(e) => {
+            log::error!("{:?}", e);
+            Detected::Missing
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn detect_macos_path(verifier: &pahkat_types::synth::macos::PathRef) -> Detected {
+    for app_path in verifier.app_paths.iter() {
+        let plist_path = Path::new(app_path).join("Contents").join("Info.plist");
+        let info = match plist::Value::from_file(&plist_path) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let info = match info.as_dictionary() {
+            Some(v) => v,
+            None => continue,
+        };
+
+        let version = info
+            .get("CFBundleShortVersionString")
+            .and_then(plist::Value::as_string);
+        let build = info
+            .get("CFBundleVersion")
+            .and_then(plist::Value::as_string);
+
+        let version_ok = match version {
+            Some(v) => within(
+                v,
+                verifier.min_version.as_ref(),
+                verifier.max_version.as_ref(),
+            ),
+            None => verifier.min_version.is_none() && verifier.max_version.is_none(),
+        };
+        let build_ok = match build {
+            Some(v) => within(v, verifier.min_build.as_ref(), verifier.max_build.as_ref()),
+            None => verifier.min_build.is_none() && verifier.max_build.is_none(),
+        };
+
+        if !version_ok || !build_ok {
+            continue;
+        }
+
+        return match version {
+            Some(v) => Detected::Version(v.to_string()),
+            None => Detected::Present,
+        };
+    }
+
+    Detected::Missing
+}
@@ -1,14 +1,19 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fmt;
 use std::sync::Arc;
 
+use futures::stream::StreamExt;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 use crate::package_store::PackageStore;
+use crate::CancellationToken;
 use pahkat_types::PackageKey;
 
+pub mod history;
 pub mod install;
+pub mod journal;
+pub mod plan;
 pub mod uninstall;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -16,6 +21,8 @@ pub enum PackageStatus {
     NotInstalled,
     UpToDate,
     RequiresUpdate,
+    /// The installed version is newer than the release that was queried for.
+    NewerThanAvailable,
 }
 
 use crate::repo::PayloadError;
@@ -26,6 +33,7 @@ pub fn status_to_i8(result: Result<PackageStatus, PackageStatusError>) -> i8 {
             PackageStatus::NotInstalled => 0,
             PackageStatus::UpToDate => 1,
             PackageStatus::RequiresUpdate => 2,
+            PackageStatus::NewerThanAvailable => 3,
         },
         Err(error) => match error {
             PackageStatusError::Payload(e) => match e {
@@ -35,6 +43,8 @@ pub fn status_to_i8(result: Result<PackageStatus, PackageStatusError>) -> i8 {
             },
             PackageStatusError::WrongPayloadType => -3,
             PackageStatusError::ParsingVersion => -4,
+            PackageStatusError::Database(_) => -6,
+            PackageStatusError::UnsupportedVerifier => -7,
         },
     }
 }
@@ -48,12 +58,13 @@ impl fmt::Display for PackageStatus {
                 PackageStatus::NotInstalled => "Not installed",
                 PackageStatus::UpToDate => "Up to date",
                 PackageStatus::RequiresUpdate => "Requires update",
+                PackageStatus::NewerThanAvailable => "Newer than available",
             }
         )
     }
 }
 
-use crate::package_store::InstallTarget;
+use crate::package_store::{InstallReason, InstallTarget};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -112,6 +123,12 @@ pub enum PackageStatusError {
 
     #[error("Error parsing version")]
     ParsingVersion,
+
+    #[error("Error reading package database: {0}")]
+    Database(String),
+
+    #[error("Verifier is not supported on this platform")]
+    UnsupportedVerifier,
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -134,8 +151,11 @@ pub enum PackageTransactionError {
     #[error("A dependency resolution error occurred")]
     Deps(#[from] PackageDependencyError),
 
-    #[error("Some transaction actions contradict: {0}")]
-    ActionContradiction(String),
+    #[error("Transaction actions contradict: uninstalling `{0}` conflicts with installing `{1}`")]
+    ActionContradiction(PackageKey, PackageKey),
+
+    #[error("The package candidates could not be resolved: {0}")]
+    Candidate(String),
 
     #[error("Invalid package status detected")]
     InvalidStatus(#[from] crate::transaction::PackageStatusError),
@@ -144,6 +164,21 @@ pub enum PackageTransactionError {
     InvalidPayload(#[from] crate::repo::PayloadError),
 }
 
+impl From<crate::repo::PackageCandidateError> for PackageTransactionError {
+    fn from(e: crate::repo::PackageCandidateError) -> Self {
+        use crate::repo::PackageCandidateError;
+
+        match e {
+            PackageCandidateError::ActionContradiction(uninstall, install)
+            | PackageCandidateError::UninstallConflict(uninstall, install) => {
+                PackageTransactionError::ActionContradiction(uninstall, install)
+            }
+            PackageCandidateError::UnresolvedId(id) => PackageTransactionError::NoPackage(id),
+            e => PackageTransactionError::Candidate(e.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PackageActionType {
@@ -168,7 +203,9 @@ impl PackageActionType {
     }
 }
 
+use self::history::{HistoryEntry, Initiator};
 use self::install::InstallError;
+use self::journal::{JournalEntry, JournalEntryState, JournalError, TransactionJournal};
 use self::uninstall::UninstallError;
 
 #[derive(Debug)]
@@ -179,7 +216,15 @@ pub enum TransactionError {
     Install(InstallError),
 }
 
-impl std::error::Error for TransactionError {}
+impl std::error::Error for TransactionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransactionError::Uninstall(e) => Some(e),
+            TransactionError::Install(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl std::fmt::Display for TransactionError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -198,7 +243,8 @@ impl std::fmt::Display for TransactionError {
 pub enum TransactionEvent {
     Installing(PackageKey),
     Uninstalling(PackageKey),
-    Progress(PackageKey, String),
+    /// Install progress of a package as `(current, total)` bytes, with a description.
+    Progress(PackageKey, (u64, u64), String),
     Error(PackageKey, TransactionError),
     Complete,
 }
@@ -214,16 +260,37 @@ pub struct ResolvedAction {
     pub descriptor: Descriptor,
     pub release: Release,
     pub target: Target,
+    /// Whether the action was only added to satisfy the dependencies of another action.
+    #[serde(default)]
+    pub is_dependency: bool,
+    /// The key of an installed package this action migrates from, when the package was
+    /// renamed or moved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<PackageKey>,
 }
 
 pub struct PackageTransaction {
     store: Arc<dyn PackageStore>,
     actions: Arc<Vec<ResolvedAction>>,
     is_reboot_required: bool,
+    cancel: CancellationToken,
+    initiator: Initiator,
 }
 
 use crate::repo::PackageCandidateError;
 
+#[derive(Debug, thiserror::Error)]
+pub enum RecoveryError {
+    #[error("No interrupted transaction was found")]
+    NotInterrupted,
+
+    #[error("Could not read the transaction journal")]
+    Journal(#[from] JournalError),
+
+    #[error("Could not resolve the recovered transaction")]
+    Candidate(#[from] PackageCandidateError),
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct ResolvedRelease {
@@ -252,6 +319,16 @@ pub struct ResolvedDescriptor {
     pub tags: Vec<String>,
     pub name: pahkat_types::LangTagMap<String>,
     pub description: pahkat_types::LangTagMap<String>,
+    /// The name in the language best matching the query's preferred languages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_description: Option<String>,
+    /// Translations of `tags` from the repository's strings, where it has them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tag_labels: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_label: Option<String>,
     pub release: ResolvedRelease,
 }
 
@@ -277,15 +354,53 @@ pub struct ResolvedPackageQuery {
 }
 
 impl PackageTransaction {
+    /// Creates a transaction for `actions`. This is refused while the journal of an
+    /// interrupted transaction remains, as processing would overwrite it; recover it with
+    /// [`PackageTransaction::resume`] or [`PackageTransaction::rollback`], or discard it.
     pub fn new(
         store: Arc<dyn PackageStore>,
         actions: Vec<PackageAction>,
+    ) -> Result<PackageTransaction, PackageCandidateError> {
+        match store.interrupted_transaction() {
+            Ok(Some(_)) => return Err(PackageCandidateError::InterruptedTransaction),
+            Ok(None) => {}
+            Err(e) => log::warn!("Could not read transaction journal: {:?}", &e),
+        }
+
+        Self::resolve(store, actions)
+    }
+
+    fn resolve(
+        store: Arc<dyn PackageStore>,
+        actions: Vec<PackageAction>,
     ) -> Result<PackageTransaction, PackageCandidateError> {
         log::debug!("New transaction with actions: {:#?}", &actions);
 
         let repos = store.repos();
         let repos = repos.read().unwrap();
 
+        // Packages that were renamed or moved are installed under their new key, replacing
+        // what is installed under the old one.
+        let mut replaced = std::collections::HashMap::new();
+        let actions = actions
+            .into_iter()
+            .map(|mut action| {
+                if action.action != PackageActionType::Install {
+                    return action;
+                }
+
+                if let Some(key) = crate::repo::resolve_redirects(&action.id, &*repos) {
+                    let old_key = action.id.clone().without_query_params();
+                    if key.clone().without_query_params() != old_key {
+                        log::debug!("{} was moved to {}", &old_key, &key);
+                        replaced.insert(key.clone(), old_key);
+                        action.id = key;
+                    }
+                }
+                action
+            })
+            .collect::<Vec<_>>();
+
         // // Get mutation set (for install and uninstall actions)
         let install_target = actions
             .iter()
@@ -302,49 +417,97 @@ impl PackageTransaction {
 
         let is_reboot_required = mutation_set.iter().any(|x| x.is_reboot_required);
 
+        // Dependencies follow the requested actions when they all share a target.
+        let dependency_target = match &*install_target {
+            [target] => *target,
+            _ => InstallTarget::System,
+        };
+
         // Create a list of resolved actions to be processed.
         let new_actions = mutation_set
             .into_iter()
             .map(|candidate| {
                 let key = candidate.package_key;
                 let action = candidate.action;
+                let requested = actions.iter().find(|x| &x.id == &key).cloned();
 
                 ResolvedAction {
                     descriptor: candidate.descriptor,
                     release: candidate.release,
                     target: candidate.target,
-                    action: actions
-                        .iter()
-                        .find(|x| &x.id == &key)
-                        .cloned()
-                        .unwrap_or_else(|| PackageAction {
-                            id: key,
-                            action,
-                            target: InstallTarget::System,
-                        }),
+                    is_dependency: requested.is_none(),
+                    replaces: replaced.get(&key).cloned(),
+                    action: requested.unwrap_or_else(|| PackageAction {
+                        id: key,
+                        action,
+                        target: dependency_target,
+                    }),
                 }
             })
             .collect::<Vec<_>>();
 
-        // Check for uninstall actions that contradict this set
-        // for action in actions
-        //     .iter()
-        //     .filter(|x| x.action == PackageActionType::Uninstall)
-        // {
-        //     if new_actions.iter().any(|x| x.action.id == action.id) {
-        //         return Err(PackageCandidateError::UninstallConflict(action.id.clone()));
-        //     }
-        // }
-
         log::debug!("Processed actions: {:#?}", &new_actions);
 
         Ok(PackageTransaction {
             store,
             actions: Arc::new(new_actions),
             is_reboot_required,
+            cancel: CancellationToken::new(),
+            initiator: Initiator::default(),
         })
     }
 
+    /// Creates a transaction for the actions an interrupted transaction did not complete.
+    pub fn resume(store: Arc<dyn PackageStore>) -> Result<PackageTransaction, RecoveryError> {
+        let journal = store
+            .interrupted_transaction()?
+            .ok_or(RecoveryError::NotInterrupted)?;
+
+        Ok(PackageTransaction::resolve(store, journal.remaining_actions())?)
+    }
+
+    /// Creates a transaction that undoes the actions an interrupted transaction started.
+    pub fn rollback(store: Arc<dyn PackageStore>) -> Result<PackageTransaction, RecoveryError> {
+        let journal = store
+            .interrupted_transaction()?
+            .ok_or(RecoveryError::NotInterrupted)?;
+
+        // An action may have been interrupted before it changed anything, so only undo
+        // what is actually reflected in the current package status.
+        let actions = journal
+            .rollback_actions()
+            .into_iter()
+            .filter(|action| is_installed(&*store, action) == action.is_uninstall())
+            .collect();
+
+        Ok(PackageTransaction::resolve(store, actions)?)
+    }
+
+    /// Creates a transaction that resolves all drift between the store and `manifest`.
+    pub fn from_manifest(
+        store: Arc<dyn PackageStore>,
+        manifest: &crate::manifest::Manifest,
+    ) -> Result<PackageTransaction, crate::manifest::ManifestError> {
+        let actions = manifest.actions(&*store)?;
+        Ok(PackageTransaction::new(store, actions)?)
+    }
+
+    /// Creates a transaction that makes the store match `lockfile`, installing the pinned
+    /// versions and removing packages the lockfile does not list.
+    pub fn from_lockfile(
+        store: Arc<dyn PackageStore>,
+        lockfile: &crate::lockfile::Lockfile,
+    ) -> Result<PackageTransaction, crate::lockfile::LockfileError> {
+        let actions = lockfile.actions(&*store)?;
+        Ok(PackageTransaction::new(store, actions)?)
+    }
+
+    /// Sets what started this transaction, as recorded in the package history.
+    pub fn with_initiator(mut self, initiator: Initiator) -> Self {
+        self.initiator = initiator;
+        self
+    }
+
     pub fn actions(&self) -> Arc<Vec<ResolvedAction>> {
         Arc::clone(&self.actions)
     }
@@ -353,24 +516,66 @@ impl PackageTransaction {
         self.is_reboot_required
     }
 
-    pub fn process(
-        &self,
-    ) -> (
-        stream_cancel::Trigger,
-        crate::package_store::Stream<TransactionEvent>,
-    ) {
+    /// Describes what processing this transaction would do, including the space it needs.
+    pub fn plan(&self) -> Result<self::plan::TransactionPlan, self::plan::PlanError> {
+        self::plan::TransactionPlan::new(&*self.store, &self.actions, self.is_reboot_required)
+    }
+
+    /// The token that cancels this transaction, including any downloads started with it.
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Processes the actions of this transaction.
+    ///
+    /// Cancelling the returned token stops the transaction at the next safe point, which
+    /// includes part way through unpacking a tarball payload.
+    pub fn process(&self) -> (CancellationToken, crate::package_store::Stream<TransactionEvent>) {
         log::debug!("beginning transaction process");
 
-        let (canceler, valve) = stream_cancel::Valve::new();
+        let cancel = self.cancellation_token();
 
         let store = Arc::clone(&self.store);
         let actions: Arc<Vec<ResolvedAction>> = Arc::clone(&self.actions);
+        let initiator = self.initiator;
         log::debug!("beginning transaction process NNNNN");
 
         let stream = async_stream::stream! {
-            for record in actions.iter() {
+            let mut journal = {
+                let entries = actions
+                    .iter()
+                    .map(|record| JournalEntry {
+                        action: record.action.clone(),
+                        state: JournalEntryState::Pending,
+                        was_installed: is_installed(&*store, &record.action),
+                    })
+                    .collect();
+
+                let config = store.config();
+                let config = config.read().unwrap();
+                match TransactionJournal::create(&*config, entries) {
+                    Ok(v) => Some(v),
+                    Err(e) => {
+                        log::error!("Could not create transaction journal: {:?}", &e);
+                        None
+                    }
+                }
+            };
+
+            for (index, record) in actions.iter().enumerate() {
                 let action = &record.action;
+
+                if cancel.is_cancelled() {
+                    yield TransactionEvent::Error(action.id.clone(), TransactionError::UserCancelled);
+                    return;
+                }
+
                 log::debug!("processing action: {}", &action);
+                update_journal(&mut journal, index, JournalEntryState::Started);
+                let from_version = store
+                    .installed_version(record.replaces.as_ref().unwrap_or(&action.id), action.target)
+                    .ok()
+                    .flatten();
 
                 match action.action {
                     PackageActionType::Install => {
@@ -378,12 +583,64 @@ impl PackageTransaction {
                         yield TransactionEvent::Installing(action.id.clone());
 
                         log::debug!("Going to install now.");
-                        match store.install(&action.id, action.target) {
+
+                        // Installs block, so run them on their own thread and forward the
+                        // progress they report until they finish.
+                        let (progress_tx, mut progress_rx) = futures::channel::mpsc::unbounded();
+                        let (result_tx, result_rx) = futures::channel::oneshot::channel();
+
+                        {
+                            let store = Arc::clone(&store);
+                            let cancel = cancel.clone();
+                            let action = action.clone();
+
+                            std::thread::spawn(move || {
+                                let progress = move |current: u64, total: u64| {
+                                    let _ = progress_tx.unbounded_send((current, total));
+                                };
+                                let result = store.install(&action.id, action.target, &cancel, &progress);
+                                let _ = result_tx.send(result);
+                            });
+                        }
+
+                        while let Some(progress) = progress_rx.next().await {
+                            yield TransactionEvent::Progress(action.id.clone(), progress, "Installing".to_string());
+                        }
+
+                        // The sender is only dropped without a result if the install panicked.
+                        let result = result_rx.await.unwrap_or(Err(InstallError::Aborted));
+
+                        match result {
                             Ok(_) => {
                                 log::trace!("We came out the other side.");
+
+                                if let Some(old_key) = record.replaces.as_ref() {
+                                    if let Err(e) = store.migrate_package(old_key, &action.id, action.target) {
+                                        log::error!("Could not migrate {} to {}: {:?}", old_key, &action.id, &e);
+                                    }
+                                }
+
+                                // Updating a package as a dependency does not change why it was installed.
+                                if !record.is_dependency || from_version.is_none() {
+                                    let reason = if record.is_dependency {
+                                        InstallReason::Dependency
+                                    } else {
+                                        InstallReason::Requested
+                                    };
+                                    if let Err(e) = store.set_install_reason(&action.id, action.target, reason) {
+                                        log::error!("Could not record install reason: {:?}", &e);
+                                    }
+                                }
+                            }
+                            Err(InstallError::UserCancelled) => {
+                                // Nothing was changed, so the action can be retried as is.
+                                update_journal(&mut journal, index, JournalEntryState::Pending);
+                                yield TransactionEvent::Error(action.id.clone(), TransactionError::UserCancelled);
+                                return;
                             }
                             Err(e) => {
                                 log::error!("{:?}", &e);
+                                update_journal(&mut journal, index, JournalEntryState::Failed);
                                 yield TransactionEvent::Error(action.id.clone(), TransactionError::Install(e));
                                 return;
                             }
@@ -396,17 +653,58 @@ impl PackageTransaction {
                             Ok(_) => {}
                             Err(e) => {
                                 log::error!("{:?}", &e);
+                                update_journal(&mut journal, index, JournalEntryState::Failed);
                                 yield TransactionEvent::Error(action.id.clone(), TransactionError::Uninstall(e));
                                 return;
                             }
                         };
                     }
                 }
+
+                update_journal(&mut journal, index, JournalEntryState::Completed);
+
+                let to_version = match action.action {
+                    PackageActionType::Install => store.installed_version(&action.id, action.target).ok().flatten(),
+                    PackageActionType::Uninstall => None,
+                };
+                record_history(&*store, &HistoryEntry::new(&action.id, action.target, from_version, to_version, initiator));
+            }
+
+            if let Some(journal) = journal.take() {
+                if let Err(e) = journal.finish() {
+                    log::error!("Could not remove transaction journal: {:?}", &e);
+                }
             }
 
             yield TransactionEvent::Complete;
         };
 
-        (canceler, Box::pin(valve.wrap(stream)))
+        (self.cancellation_token(), Box::pin(stream))
+    }
+}
+
+fn is_installed(store: &dyn PackageStore, action: &PackageAction) -> bool {
+    match store.status(&action.id, action.target) {
+        Ok(PackageStatus::NotInstalled) | Err(_) => false,
+        Ok(_) => true,
+    }
+}
+
+fn record_history(store: &dyn PackageStore, entry: &HistoryEntry) {
+    // The history is for diagnostics only, so failing to write it does not fail the action.
+    if let Err(e) = store.record_history(entry) {
+        log::error!("Could not record package history: {:?}", &e);
+    }
+}
+
+fn update_journal(
+    journal: &mut Option<TransactionJournal>,
+    index: usize,
+    state: JournalEntryState,
+) {
+    if let Some(journal) = journal.as_mut() {
+        if let Err(e) = journal.set_state(index, state) {
+            log::error!("Could not update transaction journal: {:?}", &e);
+        }
     }
 }
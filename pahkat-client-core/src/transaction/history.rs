@@ -0,0 +1,214 @@
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use pahkat_types::package::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::package_store::InstallTarget;
+use crate::{Config, PackageKey};
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("Could not read package history: {1}")]
+    Read(#[source] io::Error, PathBuf),
+
+    #[error("Could not write package history: {1}")]
+    Write(#[source] io::Error, PathBuf),
+
+    #[error("Could not parse package history: {1}")]
+    Parse(#[source] serde_json::Error, PathBuf),
+
+    #[error("Could not serialize package history")]
+    Serialize(#[source] serde_json::Error),
+
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+/// What started the transaction that changed a package.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Initiator {
+    Cli,
+    Rpc,
+    Updater,
+    Unknown,
+}
+
+impl Initiator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Initiator::Cli => "cli",
+            Initiator::Rpc => "rpc",
+            Initiator::Updater => "updater",
+            Initiator::Unknown => "unknown",
+        }
+    }
+}
+
+impl Default for Initiator {
+    fn default() -> Self {
+        Initiator::Unknown
+    }
+}
+
+impl fmt::Display for Initiator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Initiator {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "cli" => Initiator::Cli,
+            "rpc" => Initiator::Rpc,
+            "updater" => Initiator::Updater,
+            _ => Initiator::Unknown,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryEvent {
+    Install,
+    Upgrade,
+    Downgrade,
+    Reinstall,
+    Uninstall,
+}
+
+impl HistoryEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HistoryEvent::Install => "install",
+            HistoryEvent::Upgrade => "upgrade",
+            HistoryEvent::Downgrade => "downgrade",
+            HistoryEvent::Reinstall => "reinstall",
+            HistoryEvent::Uninstall => "uninstall",
+        }
+    }
+
+    /// Classifies a change of a package from one installed version to another.
+    fn from_versions(from: Option<&str>, to: Option<&str>) -> HistoryEvent {
+        let (from, to) = match (from, to) {
+            (None, _) => return HistoryEvent::Install,
+            (Some(_), None) => return HistoryEvent::Uninstall,
+            (Some(from), Some(to)) => (from, to),
+        };
+
+        match (Version::new(from), Version::new(to)) {
+            (Ok(from), Ok(to)) if to > from => HistoryEvent::Upgrade,
+            (Ok(from), Ok(to)) if to < from => HistoryEvent::Downgrade,
+            _ if from == to => HistoryEvent::Reinstall,
+            _ => HistoryEvent::Upgrade,
+        }
+    }
+}
+
+impl fmt::Display for HistoryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HistoryEvent {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "install" => Ok(HistoryEvent::Install),
+            "upgrade" => Ok(HistoryEvent::Upgrade),
+            "downgrade" => Ok(HistoryEvent::Downgrade),
+            "reinstall" => Ok(HistoryEvent::Reinstall),
+            "uninstall" => Ok(HistoryEvent::Uninstall),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A single change to an installed package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub timestamp: String,
+    /// The package key, without query parameters. Its repository URL is that of the
+    /// repository the package was installed from.
+    pub key: PackageKey,
+    pub target: InstallTarget,
+    pub event: HistoryEvent,
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+    pub initiator: Initiator,
+}
+
+impl HistoryEntry {
+    /// Records the change of a package from the `from` version to the `to` version,
+    /// where `None` means the package was not installed.
+    pub fn new(
+        key: &PackageKey,
+        target: InstallTarget,
+        from_version: Option<String>,
+        to_version: Option<String>,
+        initiator: Initiator,
+    ) -> HistoryEntry {
+        HistoryEntry {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            key: key.clone().without_query_params(),
+            target,
+            event: HistoryEvent::from_versions(from_version.as_deref(), to_version.as_deref()),
+            from_version,
+            to_version,
+            initiator,
+        }
+    }
+}
+
+/// The package history of stores without a database of their own, kept as one JSON
+/// entry per line in the store's config directory.
+pub(crate) struct HistoryLog;
+
+impl HistoryLog {
+    fn path(config: &Config) -> PathBuf {
+        config.settings().config_dir().join(HISTORY_FILE_NAME)
+    }
+
+    pub(crate) fn load(config: &Config) -> Result<Vec<HistoryEntry>, HistoryError> {
+        let path = Self::path(config);
+
+        let data = match fs::read_to_string(&path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(HistoryError::Read(e, path)),
+        };
+
+        data.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| HistoryError::Parse(e, path.clone()))
+            })
+            .collect()
+    }
+
+    pub(crate) fn append(config: &Config, entry: &HistoryEntry) -> Result<(), HistoryError> {
+        let path = Self::path(config);
+        let mut line = serde_json::to_string(entry).map_err(HistoryError::Serialize)?;
+        line.push('\n');
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| HistoryError::Write(e, path.clone()))?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| HistoryError::Write(e, path))
+    }
+}
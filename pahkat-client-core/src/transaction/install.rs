@@ -1,4 +1,4 @@
-use std::{io, process};
+use std::{io, path::PathBuf, process};
 
 #[derive(thiserror::Error, Debug)]
 pub enum InstallError {
@@ -13,6 +13,59 @@ pub enum InstallError {
 
     #[error("Installation process failed")]
     InstallerFailure(#[from] ProcessError),
+
+    #[error("Installation was cancelled")]
+    UserCancelled,
+
+    #[error("Installation ended without a result")]
+    Aborted,
+
+    #[error("Could not read package from cache: {1}")]
+    ReadPackageFailed(#[source] io::Error, PathBuf),
+
+    #[error("Could not create directory: {1}")]
+    CreateDirFailed(#[source] io::Error, PathBuf),
+
+    #[error("Could not unpack package contents")]
+    UnpackFailed(#[source] io::Error),
+
+    #[error("Package contains an invalid file path: {0}")]
+    InvalidFilePath(PathBuf),
+
+    #[error("Could not create executable shim: {1}")]
+    LinkFailed(#[source] io::Error, PathBuf),
+
+    #[error("Could not write activation script: {1}")]
+    ActivationScriptFailed(#[source] io::Error, PathBuf),
+
+    #[error("Package hook failed")]
+    Hook(#[from] HookError),
+
+    #[cfg(feature = "prefix")]
+    #[error("Error connecting to database")]
+    DatabaseConnection(#[source] r2d2::Error),
+
+    #[cfg(feature = "prefix")]
+    #[error("Error processing SQL query")]
+    Database(#[source] rusqlite::Error),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HookError {
+    #[error("Hook `{0}` has an invalid script path: {1}")]
+    InvalidPath(String, PathBuf),
+
+    #[error("Hook `{0}` script not found: {1}")]
+    NotFound(String, PathBuf),
+
+    #[error("Hook `{0}` could not be started")]
+    Spawn(String, #[source] io::Error),
+
+    #[error("Hook `{0}` failed with {1}")]
+    Failed(String, process::ExitStatus),
+
+    #[error("Hook `{0}` did not finish within {1} seconds")]
+    TimedOut(String, u64),
 }
 
 #[derive(thiserror::Error, Debug)]
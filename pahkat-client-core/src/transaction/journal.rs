@@ -0,0 +1,188 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use pahkat_types::repo::RepoUrl;
+use serde::{Deserialize, Serialize};
+
+use super::{PackageAction, PackageActionType};
+use crate::Config;
+
+const JOURNAL_FILE_NAME: &str = "transaction.json";
+
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error("Could not read transaction journal: {1}")]
+    Read(#[source] io::Error, PathBuf),
+
+    #[error("Could not write transaction journal: {1}")]
+    Write(#[source] io::Error, PathBuf),
+
+    #[error("Could not parse transaction journal: {1}")]
+    Parse(#[source] serde_json::Error, PathBuf),
+
+    #[error("Could not serialize transaction journal")]
+    Serialize(#[source] serde_json::Error),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalEntryState {
+    Pending,
+    Started,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub action: PackageAction,
+    pub state: JournalEntryState,
+    /// Whether the package was installed before the transaction began.
+    pub was_installed: bool,
+}
+
+/// A record of the actions of a transaction and how far each of them got.
+///
+/// The journal is written to the store's config directory before the first action
+/// is processed and removed once the transaction completes, so finding one on disk
+/// means a previous transaction was interrupted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionJournal {
+    pub started_on: String,
+    pub entries: Vec<JournalEntry>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl TransactionJournal {
+    fn path(config: &Config) -> PathBuf {
+        config.settings().config_dir().join(JOURNAL_FILE_NAME)
+    }
+
+    pub fn load(config: &Config) -> Result<Option<TransactionJournal>, JournalError> {
+        let path = Self::path(config);
+
+        let data = match fs::read_to_string(&path) {
+            Ok(v) => v,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(JournalError::Read(e, path)),
+        };
+
+        let mut journal: TransactionJournal =
+            serde_json::from_str(&data).map_err(|e| JournalError::Parse(e, path.clone()))?;
+        journal.path = path;
+        Ok(Some(journal))
+    }
+
+    /// Removes the journal of an interrupted transaction without acting on it.
+    pub fn discard(config: &Config) -> Result<(), JournalError> {
+        let path = Self::path(config);
+
+        match fs::remove_file(&path) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(JournalError::Write(e, path)),
+        }
+    }
+
+    pub(crate) fn create(
+        config: &Config,
+        entries: Vec<JournalEntry>,
+    ) -> Result<TransactionJournal, JournalError> {
+        let journal = TransactionJournal {
+            started_on: chrono::Utc::now().to_rfc3339(),
+            entries,
+            path: Self::path(config),
+        };
+
+        journal.save()?;
+        Ok(journal)
+    }
+
+    /// Points the actions of an interrupted transaction in the repository at `from` to the
+    /// one it has moved to, so the transaction can still be resumed or rolled back.
+    pub(crate) fn move_repo(
+        config: &Config,
+        from: &RepoUrl,
+        to: &RepoUrl,
+    ) -> Result<(), JournalError> {
+        let mut journal = match Self::load(config)? {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+
+        let mut changed = false;
+        for entry in journal.entries.iter_mut() {
+            if &entry.action.id.repository_url == from {
+                entry.action.id.repository_url = to.clone();
+                changed = true;
+            }
+        }
+
+        if changed {
+            journal.save()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_state(
+        &mut self,
+        index: usize,
+        state: JournalEntryState,
+    ) -> Result<(), JournalError> {
+        self.entries[index].state = state;
+        self.save()
+    }
+
+    pub(crate) fn finish(self) -> Result<(), JournalError> {
+        fs::remove_file(&self.path).map_err(|e| JournalError::Write(e, self.path.clone()))
+    }
+
+    /// Actions that had not completed when the transaction was interrupted.
+    pub fn remaining_actions(&self) -> Vec<PackageAction> {
+        self.entries
+            .iter()
+            .filter(|x| x.state != JournalEntryState::Completed)
+            .map(|x| x.action.clone())
+            .collect()
+    }
+
+    /// Actions that undo whatever the interrupted transaction managed to do, in reverse order.
+    ///
+    /// Packages that were updated rather than freshly installed cannot be restored to their
+    /// previous version, and are left as they are.
+    pub fn rollback_actions(&self) -> Vec<PackageAction> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|x| x.state != JournalEntryState::Pending)
+            .filter_map(|x| match x.action.action {
+                PackageActionType::Install if !x.was_installed => Some(PackageAction::uninstall(
+                    x.action.id.clone(),
+                    x.action.target,
+                )),
+                PackageActionType::Uninstall if x.was_installed => Some(PackageAction::install(
+                    x.action.id.clone(),
+                    x.action.target,
+                )),
+                _ => {
+                    log::warn!("Cannot roll back action: {}", &x.action);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn save(&self) -> Result<(), JournalError> {
+        let data = serde_json::to_vec_pretty(self).map_err(JournalError::Serialize)?;
+
+        // Write to a temporary file first so a crash never leaves a truncated journal.
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, &data).map_err(|e| JournalError::Write(e, tmp_path.clone()))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| JournalError::Write(e, self.path.clone()))
+    }
+}
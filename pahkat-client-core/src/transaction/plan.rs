@@ -0,0 +1,245 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{PackageAction, PackageActionType, PackageStatusError, ResolvedAction};
+use crate::package_store::PackageStore;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PlanError {
+    #[error("Not enough free space on the volume containing {path}: {required} bytes required, {available} bytes available")]
+    InsufficientSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+
+    #[error("Could not determine free space for {1}")]
+    FreeSpace(#[source] io::Error, PathBuf),
+
+    #[error("Invalid package status detected")]
+    InvalidStatus(#[from] PackageStatusError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedAction {
+    pub action: PackageAction,
+    pub from_version: Option<String>,
+    pub to_version: Option<String>,
+    /// Bytes still to be downloaded; zero if the payload is already cached.
+    pub download_size: u64,
+    /// The change in installed size. Updates count as zero, as the size of the
+    /// installed version is not known.
+    pub installed_size_delta: i64,
+}
+
+/// Space required on a single volume for a transaction to complete.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpaceRequirement {
+    pub path: PathBuf,
+    pub required: u64,
+    pub available: u64,
+}
+
+impl SpaceRequirement {
+    #[inline]
+    pub fn is_satisfied(&self) -> bool {
+        self.required <= self.available
+    }
+}
+
+/// What a transaction would do if processed, without changing anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionPlan {
+    pub actions: Vec<PlannedAction>,
+    pub is_reboot_required: bool,
+    pub download_size: u64,
+    pub installed_size_delta: i64,
+    pub space: Vec<SpaceRequirement>,
+}
+
+impl TransactionPlan {
+    pub(crate) fn new(
+        store: &dyn PackageStore,
+        actions: &[ResolvedAction],
+        is_reboot_required: bool,
+    ) -> Result<TransactionPlan, PlanError> {
+        let config = store.config();
+        let config = config.read().unwrap();
+
+        let mut planned = Vec::with_capacity(actions.len());
+        let mut staging_size = 0u64;
+
+        for record in actions.iter() {
+            let action = &record.action;
+            let payload = &record.target.payload;
+            let from_version = store.installed_version(&action.id, action.target)?;
+
+            let item = match action.action {
+                PackageActionType::Install => {
+                    let path = crate::repo::download_file_path(&*config, payload.url());
+                    let download_size = if path.exists() { 0 } else { payload.size() };
+                    let installed_size_delta = if from_version.is_some() {
+                        0
+                    } else {
+                        payload.installed_size() as i64
+                    };
+
+                    // The new files are unpacked beside the old ones before being moved
+                    // into place, so the whole installed size is needed up front.
+                    staging_size += payload.installed_size();
+
+                    PlannedAction {
+                        action: action.clone(),
+                        from_version,
+                        to_version: Some(record.release.version.to_string()),
+                        download_size,
+                        installed_size_delta,
+                    }
+                }
+                PackageActionType::Uninstall => PlannedAction {
+                    action: action.clone(),
+                    from_version,
+                    to_version: None,
+                    download_size: 0,
+                    installed_size_delta: -(payload.installed_size() as i64),
+                },
+            };
+
+            planned.push(item);
+        }
+
+        let download_size = planned.iter().map(|x| x.download_size).sum();
+        let installed_size_delta = planned.iter().map(|x| x.installed_size_delta).sum();
+
+        let mut volumes: Vec<(PathBuf, u64)> = vec![];
+        if download_size > 0 {
+            volumes.push((config.settings().package_cache_dir(), download_size));
+        }
+        if let Some(dir) = store.install_dir() {
+            if staging_size > 0 {
+                volumes.push((dir, staging_size));
+            }
+        }
+
+        let mut space: Vec<(u64, SpaceRequirement)> = vec![];
+        for (path, required) in volumes.into_iter() {
+            let (volume, available) =
+                free_space(&path).map_err(|e| PlanError::FreeSpace(e, path.clone()))?;
+
+            match space.iter_mut().find(|(id, _)| *id == volume) {
+                Some((_, existing)) => existing.required += required,
+                None => space.push((
+                    volume,
+                    SpaceRequirement {
+                        path,
+                        required,
+                        available,
+                    },
+                )),
+            }
+        }
+
+        Ok(TransactionPlan {
+            actions: planned,
+            is_reboot_required,
+            download_size,
+            installed_size_delta,
+            space: space.into_iter().map(|(_, x)| x).collect(),
+        })
+    }
+
+    /// Fails if any volume lacks the space the transaction needs.
+    pub fn validate(&self) -> Result<(), PlanError> {
+        match self.space.iter().find(|x| !x.is_satisfied()) {
+            Some(x) => Err(PlanError::InsufficientSpace {
+                path: x.path.clone(),
+                required: x.required,
+                available: x.available,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Directories may not have been created yet, so query the nearest one that exists.
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|x| x.exists())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Returns an identifier for the volume containing `path` and its available bytes.
+#[cfg(unix)]
+fn free_space(path: &Path) -> io::Result<(u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let path = existing_ancestor(path);
+    let volume = std::fs::metadata(path)?.dev();
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok((volume, stat.f_bavail as u64 * stat.f_frsize as u64))
+}
+
+/// Returns an identifier for the volume containing `path` and its available bytes.
+#[cfg(windows)]
+fn free_space(path: &Path) -> io::Result<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::um::fileapi::{GetDiskFreeSpaceExW, GetVolumeInformationW, GetVolumePathNameW};
+
+    let path = existing_ancestor(path);
+    let wide_path = path
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect::<Vec<u16>>();
+
+    let mut root = vec![0u16; wide_path.len().max(261)];
+    let mut serial = 0u32;
+    let mut available = 0u64;
+
+    unsafe {
+        if GetVolumePathNameW(wide_path.as_ptr(), root.as_mut_ptr(), root.len() as u32) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if GetVolumeInformationW(
+            root.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            &mut serial,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        ) == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+
+        if GetDiskFreeSpaceExW(
+            wide_path.as_ptr(),
+            &mut available as *mut u64 as *mut _,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        ) == 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok((serial as u64, available))
+}
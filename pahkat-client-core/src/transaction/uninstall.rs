@@ -1,4 +1,6 @@
-use super::install::ProcessError;
+use std::{io, path::PathBuf};
+
+use super::install::{HookError, ProcessError};
 
 #[derive(thiserror::Error, Debug)]
 pub enum UninstallError {
@@ -16,4 +18,24 @@ pub enum UninstallError {
 
     #[error("The package is not installed")]
     NotInstalled,
+
+    #[error("Could not remove file: {1}")]
+    RemoveFileFailed(#[source] io::Error, PathBuf),
+
+    #[error("Could not remove directory: {1}")]
+    RemoveDirFailed(#[source] io::Error, PathBuf),
+
+    #[error("Could not write activation script: {1}")]
+    ActivationScriptFailed(#[source] io::Error, PathBuf),
+
+    #[error("Package hook failed")]
+    Hook(#[from] HookError),
+
+    #[cfg(feature = "prefix")]
+    #[error("Error connecting to database")]
+    DatabaseConnection(#[source] r2d2::Error),
+
+    #[cfg(feature = "prefix")]
+    #[error("Error processing SQL query")]
+    Database(#[source] rusqlite::Error),
 }
@@ -53,6 +53,40 @@ impl RepoIndexCommand {
     }
 }
 
+/// Parses a `KEY=LABEL` pair. An empty label removes the translation.
+fn parse_label(value: &str) -> Result<(String, String), String> {
+    let mut parts = value.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(key), Some(label)) if !key.is_empty() => Ok((key.to_string(), label.to_string())),
+        _ => Err(format!("expected KEY=LABEL, got `{}`", value)),
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct RepoStringsCommand {
+    language: Option<String>,
+
+    #[structopt(short, long, parse(try_from_str = parse_label))]
+    tag: Vec<(String, String)>,
+
+    #[structopt(short, long, parse(try_from_str = parse_label))]
+    channel: Vec<(String, String)>,
+
+    #[structopt(short = "-r", long, parse(from_os_str))]
+    repo_path: Option<PathBuf>,
+}
+
+impl RepoStringsCommand {
+    fn to_partial<'a>(&'a self) -> repo::strings::PartialRequest<'a> {
+        repo::strings::PartialRequest::builder()
+            .language(self.language.as_ref().map(|x| &**x))
+            .tags(Some(&self.tag))
+            .channels(Some(&self.channel))
+            .repo_path(self.repo_path.as_ref().map(|x| &**x))
+            .build()
+    }
+}
+
 #[derive(Debug, StructOpt)]
 struct PackageInitCommand {
     id: Option<String>,
@@ -123,6 +157,7 @@ impl PackageUpdateCommand {
 enum RepoCommand {
     Init(RepoInitCommand),
     Index(RepoIndexCommand),
+    Strings(RepoStringsCommand),
 }
 
 #[derive(Debug, StructOpt)]
@@ -176,6 +211,10 @@ fn main() -> anyhow::Result<()> {
                 let req = repo::indexing::Request::new_from_user_input(index.to_partial())?;
                 repo::indexing::index(req)?;
             }
+            RepoCommand::Strings(strings) => {
+                let req = repo::strings::Request::new_from_user_input(strings.to_partial())?;
+                repo::strings::strings(req)?;
+            }
         },
         Command::Package(package) => match package {
             PackageCommand::Init(init) => {
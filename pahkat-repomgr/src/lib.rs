@@ -17,6 +17,7 @@ pub trait Request {
 
 pub fn make_lang_tag_map(value: String) -> pahkat_types::LangTagMap<String> {
     let mut map = pahkat_types::LangTagMap::new();
-    map.insert("en".into(), value);
+    map.insert("en".into(), value)
+        .expect("`en` is a valid language tag");
     map
 }
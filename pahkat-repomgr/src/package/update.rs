@@ -234,6 +234,11 @@ pub fn update<'a>(request: Request<'a>) -> Result<(), Error> {
 
     let channel = request.channel.as_ref().map(|x| x.deref().to_string());
 
+    let platform = pahkat_types::Platform::from(&*request.platform);
+    if !platform.is_known() {
+        log::warn!("Unknown platform `{}`; clients may never resolve this target", &platform);
+    }
+
     // Check if a release exists that meets this criteria
     let mut release = match descriptor.release.iter_mut().find(|x| &x.version == &*request.version && x.channel == channel) {
         Some(release) => {
@@ -252,7 +257,7 @@ pub fn update<'a>(request: Request<'a>) -> Result<(), Error> {
     };
 
     // Check if a target exists that meets this criteria
-    let mut target = match release.target.iter_mut().find(|x| x.platform == request.platform) {
+    let mut target = match release.target.iter_mut().find(|x| x.platform == platform) {
         Some(target) => {
             log::info!("Found target!");
             target.payload = request.payload.deref().clone();
@@ -261,7 +266,7 @@ pub fn update<'a>(request: Request<'a>) -> Result<(), Error> {
         None => {
             log::info!("No target; creating.");
             release.target.insert(0, pahkat_types::payload::Target::builder()
-                .platform(request.platform.to_string())
+                .platform(platform)
                 .payload(request.payload.deref().clone()).build());
             release.target.first_mut().unwrap()
         } 
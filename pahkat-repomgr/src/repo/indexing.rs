@@ -11,6 +11,7 @@ pub fn index(request: Request<'_>) -> anyhow::Result<()> {
     // Attempt to make strings directory if it doesn't exist
     let strings_path = request.path.join("strings");
     std::fs::create_dir_all(&strings_path)?;
+    validate_strings(&strings_path)?;
 
     // Find all package descriptor TOMLs
     let packages = std::fs::read_dir(&*packages_path)?
@@ -41,6 +42,7 @@ pub fn index(request: Request<'_>) -> anyhow::Result<()> {
                     return None;
                 }
             };
+            warn_unknown_targets(&path, &package);
             Some(package)
         })
         .collect::<Vec<pahkat_types::package::Package>>();
@@ -54,6 +56,74 @@ pub fn index(request: Request<'_>) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Checks each file in `strings/` is named after a language tag and parses, so mistakes are
+/// found before clients download the strings. Invalid files are reported and skipped.
+fn validate_strings(strings_path: &Path) -> anyhow::Result<()> {
+    use pahkat_types::lang_tag::is_valid_lang_tag;
+
+    for entry in std::fs::read_dir(strings_path)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.extension().and_then(|x| x.to_str()) != Some("toml") {
+            continue;
+        }
+
+        let language = path.file_stem().and_then(|x| x.to_str()).unwrap_or("");
+        if !is_valid_lang_tag(language) {
+            log::error!("Not named after a BCP 47 language tag: {:?}", &path);
+            log::error!("Continuing.");
+            continue;
+        }
+
+        let file = match std::fs::read_to_string(&path) {
+            Ok(v) => v,
+            Err(e) => {
+                log::error!("Could not handle path: {:?}", &path);
+                log::error!("{}", e);
+                log::error!("Continuing.");
+                continue;
+            }
+        };
+
+        if let Err(e) = toml::from_str::<pahkat_types::repo::Localisation>(&file) {
+            log::error!("Could not parse: {:?}", &path);
+            log::error!("{}", e);
+            log::error!("Continuing.");
+        }
+    }
+
+    Ok(())
+}
+
+/// Warns of targets for a platform or architecture pahkat does not know, which are most
+/// likely typos that no client will ever resolve.
+fn warn_unknown_targets(path: &Path, package: &pahkat_types::package::Package) {
+    use pahkat_types::package::Package;
+    use pahkat_types::{Arch, Platform};
+
+    let warn = |platform: &Platform, arch: Option<&Arch>| {
+        if !platform.is_known() {
+            log::warn!("Unknown platform `{}` in {:?}", platform, path);
+        }
+        if let Some(arch) = arch.filter(|x| !x.is_known()) {
+            log::warn!("Unknown architecture `{}` in {:?}", arch, path);
+        }
+    };
+
+    match package {
+        Package::Concrete(p) => {
+            for target in p.release.iter().flat_map(|x| x.target.iter()) {
+                warn(&target.platform, target.arch.as_ref());
+            }
+        }
+        Package::Synthetic(p) => {
+            for target in p.releases.iter().flat_map(|x| x.targets.iter()) {
+                warn(&target.platform, target.arch.as_ref());
+            }
+        }
+        Package::Redirect(_) => {}
+    }
+}
+
 #[non_exhaustive]
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct Request<'a> {
@@ -93,6 +163,21 @@ fn vectorize_strings<'a>(
     builder.end_vector::<butte::WIPOffset<&'_ str>>(len)
 }
 
+fn vectorize_optional_strings<'a>(
+    values: &[String],
+    builder: &mut FlatBufferBuilder<'a>,
+) -> Option<butte::WIPOffset<butte::Vector<'a, butte::WIPOffset<&'a str>>>> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let values = values
+        .iter()
+        .map(|x| builder.create_string(x.as_str()))
+        .collect::<Vec<_>>();
+    Some(vectorize_strings(values, builder))
+}
+
 fn vectorize_lang_map<'a, 'd>(
     lang_map: &'d pahkat_types::LangTagMap<String>,
     lang_keys: &mut std::collections::HashMap<&'d str, butte::WIPOffset<&'a str>>,
@@ -227,10 +312,43 @@ fn create_payload_tarball_pkg<'a>(
 ) -> butte::WIPOffset<butte::UnionWIPOffset> {
     println!("Tarball: {}", &payload.url);
     let url = builder.create_string(payload.url.as_str());
+    let pre_install = payload
+        .pre_install
+        .as_ref()
+        .map(|x| builder.create_string(x.as_str()));
+    let post_install = payload
+        .post_install
+        .as_ref()
+        .map(|x| builder.create_string(x.as_str()));
+    let pre_uninstall = payload
+        .pre_uninstall
+        .as_ref()
+        .map(|x| builder.create_string(x.as_str()));
+    let post_uninstall = payload
+        .post_uninstall
+        .as_ref()
+        .map(|x| builder.create_string(x.as_str()));
+    let executables = if payload.executables.is_empty() {
+        None
+    } else {
+        let executables = payload
+            .executables
+            .iter()
+            .map(|x| builder.create_string(x.as_str()))
+            .collect::<Vec<_>>();
+        Some(vectorize_strings(executables, builder))
+    };
+
     let args = crate::fbs::pahkat::TarballPackageArgs {
         url,
         size: payload.size,
         installed_size: payload.installed_size,
+        pre_install,
+        post_install,
+        pre_uninstall,
+        post_uninstall,
+        hook_timeout: payload.hook_timeout.unwrap_or(0),
+        executables,
     };
 
     crate::fbs::pahkat::TarballPackage::create(builder, &args).as_union_value()
@@ -243,7 +361,7 @@ fn create_targets<'d, 'a>(
     let targets = targets
         .iter()
         .map(|target| {
-            let platform = builder.create_string(&target.platform);
+            let platform = builder.create_string(target.platform.as_str());
 
             // TODO: cache keys
             let (dependencies_keys, dependencies_values): (Vec<_>, Vec<_>) = target
@@ -260,7 +378,21 @@ fn create_targets<'d, 'a>(
                 )
             };
 
-            let arch = target.arch.as_ref().map(|x| builder.create_string(&x));
+            let arch = target
+                .arch
+                .as_ref()
+                .map(|x| builder.create_string(x.as_str()));
+            let conflicts = vectorize_optional_strings(&target.conflicts, builder);
+            let provides = vectorize_optional_strings(&target.provides, builder);
+            let replaces = vectorize_optional_strings(&target.replaces, builder);
+            let min_os_version = target
+                .min_os_version
+                .as_ref()
+                .map(|x| builder.create_string(&x));
+            let max_os_version = target
+                .max_os_version
+                .as_ref()
+                .map(|x| builder.create_string(&x));
 
             use crate::fbs::pahkat::butte_gen::PayloadType;
             use pahkat_types::payload::Payload;
@@ -286,6 +418,11 @@ fn create_targets<'d, 'a>(
                 arch,
                 dependencies_keys,
                 dependencies_values,
+                conflicts,
+                provides,
+                replaces,
+                min_os_version,
+                max_os_version,
                 payload_type,
                 payload,
             };
@@ -311,13 +448,8 @@ fn create_releases<'d, 'a>(
     let releases = releases
         .iter()
         .map(|release| {
-            // TODO: handle version type properly
-            use pahkat_types::package::version::Version;
-            let (version_type, version) = match &release.version {
-                // Version::Opaque => 1u8,
-                Version::Semantic(v) => (2u8, v.to_string()),
-                _ => panic!(),
-            };
+            let version_type = release.version.version_type();
+            let version = release.version.to_string();
             let version = *release_keys
                 .entry(version.clone())
                 .or_insert_with(|| builder.create_string(&*version));
@@ -376,18 +508,221 @@ fn create_releases<'d, 'a>(
     builder.end_vector::<butte::WIPOffset<crate::fbs::pahkat::Release<&'_ [u8]>>>(len)
 }
 
+fn create_verifier<'a>(
+    verifier: &pahkat_types::synth::Verifier,
+    builder: &mut FlatBufferBuilder<'a>,
+) -> (
+    crate::fbs::pahkat::butte_gen::VerifierType,
+    butte::WIPOffset<butte::UnionWIPOffset>,
+) {
+    use crate::fbs::pahkat::butte_gen::VerifierType;
+    use pahkat_types::synth::Verifier;
+
+    match verifier {
+        Verifier::WindowsRegistryKey(v) => {
+            let args = crate::fbs::pahkat::WindowsRegistryKeyRefArgs {
+                path: builder.create_string(&v.path),
+                name: builder.create_string(&v.name),
+            };
+            (
+                VerifierType::WindowsRegistryKeyRef,
+                crate::fbs::pahkat::WindowsRegistryKeyRef::create(builder, &args).as_union_value(),
+            )
+        }
+        Verifier::MacOSPackageRef(v) => {
+            let args = crate::fbs::pahkat::MacOSPackageRefArgs {
+                pkg_id: builder.create_string(&v.pkg_id),
+                min_version: v.min_version.as_ref().map(|x| builder.create_string(x)),
+                max_version: v.max_version.as_ref().map(|x| builder.create_string(x)),
+                min_build: v.min_build.as_ref().map(|x| builder.create_string(x)),
+                max_build: v.max_build.as_ref().map(|x| builder.create_string(x)),
+            };
+            (
+                VerifierType::MacOSPackageRef,
+                crate::fbs::pahkat::MacOSPackageRef::create(builder, &args).as_union_value(),
+            )
+        }
+        Verifier::MacOSPathRef(v) => {
+            let app_paths = v
+                .app_paths
+                .iter()
+                .map(|x| builder.create_string(x))
+                .collect::<Vec<_>>();
+            let args = crate::fbs::pahkat::MacOSPathRefArgs {
+                app_paths: Some(vectorize_strings(app_paths, builder)),
+                min_version: v.min_version.as_ref().map(|x| builder.create_string(x)),
+                max_version: v.max_version.as_ref().map(|x| builder.create_string(x)),
+                min_build: v.min_build.as_ref().map(|x| builder.create_string(x)),
+                max_build: v.max_build.as_ref().map(|x| builder.create_string(x)),
+            };
+            (
+                VerifierType::MacOSPathRef,
+                crate::fbs::pahkat::MacOSPathRef::create(builder, &args).as_union_value(),
+            )
+        }
+        Verifier::FileRef(v) => {
+            let args = crate::fbs::pahkat::FileRefArgs {
+                path: builder.create_string(&v.path),
+                version_file: v.version_file.as_ref().map(|x| builder.create_string(x)),
+            };
+            (
+                VerifierType::FileRef,
+                crate::fbs::pahkat::FileRef::create(builder, &args).as_union_value(),
+            )
+        }
+        _ => panic!("Verifier must exist"),
+    }
+}
+
+fn create_synthetic_targets<'d, 'a>(
+    targets: &'d Vec<pahkat_types::synth::Target>,
+    builder: &mut FlatBufferBuilder<'a>,
+) -> butte::WIPOffset<
+    butte::Vector<'a, butte::WIPOffset<crate::fbs::pahkat::SyntheticTarget<&'a [u8]>>>,
+> {
+    let targets = targets
+        .iter()
+        .map(|target| {
+            let platform = builder.create_string(target.platform.as_str());
+
+            let (dependencies_keys, dependencies_values): (Vec<_>, Vec<_>) = target
+                .dependencies
+                .iter()
+                .map(|(key, value)| (builder.create_string(&key), builder.create_string(&value)))
+                .unzip();
+            let (dependencies_keys, dependencies_values) = if dependencies_keys.is_empty() {
+                (None, None)
+            } else {
+                (
+                    Some(vectorize_strings(dependencies_keys, builder)),
+                    Some(vectorize_strings(dependencies_values, builder)),
+                )
+            };
+
+            let arch = target
+                .arch
+                .as_ref()
+                .map(|x| builder.create_string(x.as_str()));
+            let (verifier_type, verifier) = create_verifier(&target.verifier, builder);
+
+            let args = crate::fbs::pahkat::SyntheticTargetArgs {
+                platform,
+                arch,
+                dependencies_keys,
+                dependencies_values,
+                verifier_type,
+                verifier,
+            };
+
+            crate::fbs::pahkat::SyntheticTarget::create(builder, &args)
+        })
+        .collect::<Vec<_>>();
+
+    let len = targets.len();
+    builder.start_vector::<butte::WIPOffset<crate::fbs::pahkat::SyntheticTarget<&'_ [u8]>>>(len);
+    for target in targets.into_iter().rev() {
+        builder.push(target);
+    }
+    builder.end_vector::<butte::WIPOffset<crate::fbs::pahkat::SyntheticTarget<&'_ [u8]>>>(len)
+}
+
+fn create_synthetic<'d, 'a>(
+    id: butte::WIPOffset<&'a str>,
+    descriptor: &'d pahkat_types::synth::Descriptor,
+    str_keys: &mut std::collections::HashMap<&'d str, butte::WIPOffset<&'a str>>,
+    builder: &mut FlatBufferBuilder<'a>,
+) -> butte::WIPOffset<crate::fbs::pahkat::Synthetic<&'a [u8]>> {
+    let tags = if descriptor.synthetic.tags.is_empty() {
+        None
+    } else {
+        let tags = descriptor
+            .synthetic
+            .tags
+            .iter()
+            .map(|x| {
+                *str_keys
+                    .entry(&**x)
+                    .or_insert_with(|| builder.create_string(&*x))
+            })
+            .collect::<Vec<_>>();
+        Some(vectorize_strings(tags, builder))
+    };
+
+    let (name_keys, name_values) = vectorize_lang_map(&descriptor.name, str_keys, builder);
+    let (description_keys, description_values) =
+        vectorize_lang_map(&descriptor.description, str_keys, builder);
+
+    let releases = descriptor
+        .releases
+        .iter()
+        .map(|release| {
+            let version = builder.create_string(&release.version);
+            // An empty channel is the default channel
+            let channel = if release.channel.is_empty() {
+                None
+            } else {
+                Some(
+                    *str_keys
+                        .entry(&*release.channel)
+                        .or_insert_with(|| builder.create_string(&release.channel)),
+                )
+            };
+            let target = Some(create_synthetic_targets(&release.targets, builder));
+
+            let args = crate::fbs::pahkat::SyntheticReleaseArgs {
+                version,
+                channel,
+                target,
+            };
+            crate::fbs::pahkat::SyntheticRelease::create(builder, &args)
+        })
+        .collect::<Vec<_>>();
+
+    let len = releases.len();
+    builder.start_vector::<butte::WIPOffset<crate::fbs::pahkat::SyntheticRelease<&'_ [u8]>>>(len);
+    for release in releases.into_iter().rev() {
+        builder.push(release);
+    }
+    let release = Some(
+        builder.end_vector::<butte::WIPOffset<crate::fbs::pahkat::SyntheticRelease<&'_ [u8]>>>(len),
+    );
+
+    let args = crate::fbs::pahkat::SyntheticArgs {
+        id,
+        name_keys,
+        name_values,
+        description_keys,
+        description_values,
+        tags,
+        release,
+    };
+    crate::fbs::pahkat::Synthetic::create(builder, &args)
+}
+
 fn build_index<'a>(
     builder: &'a mut FlatBufferBuilder<'a>,
     packages: &[pahkat_types::package::Package],
 ) -> anyhow::Result<&'a [u8]> {
+    use pahkat_types::package::Package;
+
     let mut owned_keys = std::collections::HashMap::new();
     let mut str_keys = std::collections::HashMap::new();
 
+    let mut descriptors = vec![];
+    let mut synthetics = vec![];
+    let mut redirects = vec![];
+    for package in packages.iter() {
+        match package {
+            Package::Concrete(p) => descriptors.push(p),
+            Package::Synthetic(p) => synthetics.push(p),
+            Package::Redirect(p) => redirects.push(p),
+        }
+    }
+
     // Use the count to create the vectors we need
-    let id_refs = packages
+    let id_refs = descriptors
         .iter()
-        .map(pahkat_types::package::Package::id)
-        .map(|id| builder.create_string(id))
+        .map(|x| builder.create_string(&x.package.id))
         .collect::<Vec<_>>();
 
     builder.start_vector::<butte::WIPOffset<&'_ str>>(id_refs.len());
@@ -404,13 +739,8 @@ fn build_index<'a>(
 
     let packages_values = id_refs
         .iter()
-        .zip(packages.iter())
-        .map(|(id_ref, package)| {
-            let descriptor = match package {
-                pahkat_types::package::Package::Concrete(p) => p,
-                _ => panic!("Unsupported package type"),
-            };
-
+        .zip(descriptors.iter().copied())
+        .map(|(id_ref, descriptor)| {
             let tags = if descriptor.package.tags.is_empty() {
                 None
             } else {
@@ -464,10 +794,71 @@ fn build_index<'a>(
         ),
     );
 
+    let synthetic_id_refs = synthetics
+        .iter()
+        .map(|x| builder.create_string(&x.synthetic.id))
+        .collect::<Vec<_>>();
+
+    let synthetics_keys = if synthetic_id_refs.is_empty() {
+        None
+    } else {
+        Some(vectorize_strings(synthetic_id_refs.clone(), builder))
+    };
+
+    let synthetics_values = synthetic_id_refs
+        .iter()
+        .zip(synthetics.iter().copied())
+        .map(|(id_ref, descriptor)| {
+            create_synthetic(id_ref.clone(), descriptor, &mut str_keys, builder)
+        })
+        .collect::<Vec<_>>();
+
+    let synthetics_values = if synthetics_values.is_empty() {
+        None
+    } else {
+        let len = synthetics_values.len();
+        builder.start_vector::<butte::WIPOffset<crate::fbs::pahkat::Synthetic<&'_ [u8]>>>(len);
+        for synthetic in synthetics_values.into_iter().rev() {
+            builder.push(synthetic);
+        }
+        Some(builder.end_vector::<butte::WIPOffset<crate::fbs::pahkat::Synthetic<&'_ [u8]>>>(len))
+    };
+
+    let (redirects_keys, redirects_values) = if redirects.is_empty() {
+        (None, None)
+    } else {
+        let (keys, values): (Vec<_>, Vec<_>) = redirects
+            .iter()
+            .map(|redirect| {
+                let id = builder.create_string(&redirect.redirect.id);
+                let url = builder.create_string(redirect.redirect.url.as_str());
+                let value = crate::fbs::pahkat::Redirect::create(
+                    builder,
+                    &crate::fbs::pahkat::RedirectArgs { url },
+                );
+                (id, value)
+            })
+            .unzip();
+
+        let keys = vectorize_strings(keys, builder);
+        let len = values.len();
+        builder.start_vector::<butte::WIPOffset<crate::fbs::pahkat::Redirect<&'_ [u8]>>>(len);
+        for value in values.into_iter().rev() {
+            builder.push(value);
+        }
+        let values =
+            builder.end_vector::<butte::WIPOffset<crate::fbs::pahkat::Redirect<&'_ [u8]>>>(len);
+        (Some(keys), Some(values))
+    };
+
     let args = crate::fbs::pahkat::PackagesArgs {
         packages_values_types,
         packages_keys,
         packages_values,
+        synthetics_keys,
+        synthetics_values,
+        redirects_keys,
+        redirects_values,
     };
 
     let root = crate::fbs::pahkat::Packages::create(builder, &args);
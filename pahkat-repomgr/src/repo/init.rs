@@ -156,10 +156,13 @@ pub fn init<'a>(request: Request<'a>) -> Result<(), Error> {
 
     // Create empty repository index
     let mut name = LangTagMap::new();
-    name.insert("en".into(), request.name.to_string());
+    name.insert("en".into(), request.name.to_string())
+        .expect("`en` is a valid language tag");
 
     let mut description = LangTagMap::new();
-    description.insert("en".into(), request.description.to_string());
+    description
+        .insert("en".into(), request.description.to_string())
+        .expect("`en` is a valid language tag");
 
     let data = RepositoryData::builder()
         .url(request.url.into_owned())
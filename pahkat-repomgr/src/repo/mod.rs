@@ -1,3 +1,4 @@
 pub mod indexing;
 pub mod init;
+pub mod strings;
 pub mod validate;
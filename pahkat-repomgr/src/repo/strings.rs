@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::{self, create_dir_all};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pahkat_types::lang_tag::is_valid_lang_tag;
+use pahkat_types::repo::Localisation;
+use typed_builder::TypedBuilder;
+
+/// Adds, changes or removes translations in `strings/<language>.toml`. A label that is
+/// empty removes the translation of its tag or channel.
+#[non_exhaustive]
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Request<'a> {
+    pub repo_path: Cow<'a, Path>,
+    pub language: Cow<'a, str>,
+    pub tags: Cow<'a, [(String, String)]>,
+    pub channels: Cow<'a, [(String, String)]>,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, TypedBuilder)]
+pub struct PartialRequest<'a> {
+    #[builder(default)]
+    pub repo_path: Option<&'a Path>,
+    #[builder(default)]
+    pub language: Option<&'a str>,
+    #[builder(default)]
+    pub tags: Option<&'a [(String, String)]>,
+    #[builder(default)]
+    pub channels: Option<&'a [(String, String)]>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    #[error("Provided path was invalid")]
+    PathError(#[source] io::Error),
+
+    #[error("Could not find repository at provided path")]
+    NoRepo(#[from] FindRepoError),
+
+    #[error("Invalid BCP 47 language tag: {0:?}")]
+    InvalidLanguage(String),
+
+    #[error("Invalid input")]
+    InvalidInput,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FindRepoError {
+    #[error("IO error")]
+    Io(#[from] io::Error),
+
+    #[error("No repository found for given path")]
+    NotFound,
+}
+
+fn open_repo(path: &Path) -> Option<pahkat_types::repo::Repository> {
+    let file = fs::read_to_string(path.join("index.toml")).ok()?;
+    let repo: pahkat_types::repo::Repository = toml::from_str(&file).ok()?;
+    Some(repo)
+}
+
+fn find_repo(path: &Path) -> Result<&Path, FindRepoError> {
+    let mut path = path;
+
+    if path.ends_with("index.toml") {
+        path = path.parent().unwrap();
+    }
+
+    if let Some(_) = open_repo(path) {
+        return Ok(path);
+    }
+
+    while let Some(parent) = path.parent() {
+        path = parent;
+        if let Some(_) = open_repo(path) {
+            return Ok(path);
+        }
+    }
+
+    Err(FindRepoError::NotFound)
+}
+
+/// Prompts for labels until an empty key is given.
+fn prompt_labels(kind: &str) -> Result<Vec<(String, String)>, RequestError> {
+    use dialoguer::Input;
+
+    let mut labels = vec![];
+
+    loop {
+        let key = Input::<String>::new()
+            .with_prompt(&format!("{} to translate (empty to finish)", kind))
+            .allow_empty(true)
+            .interact()
+            .map_err(|_| RequestError::InvalidInput)?;
+
+        if key.trim() == "" {
+            return Ok(labels);
+        }
+
+        let label = Input::<String>::new()
+            .with_prompt(&format!("Label for `{}` (empty to remove)", key.trim()))
+            .allow_empty(true)
+            .interact()
+            .map_err(|_| RequestError::InvalidInput)?;
+
+        labels.push((key.trim().to_string(), label));
+    }
+}
+
+impl<'a> crate::Request for Request<'a> {
+    type Error = RequestError;
+    type Partial = PartialRequest<'a>;
+
+    fn new_from_user_input(partial: Self::Partial) -> Result<Self, Self::Error> {
+        use dialoguer::Input;
+
+        let repo_path = match partial.repo_path {
+            Some(path) => Cow::Borrowed(path),
+            None => Input::<String>::new()
+                .default(
+                    std::env::current_dir()
+                        .ok()
+                        .and_then(|x| x.to_str().map(str::to_string))
+                        .unwrap_or_else(|| ".".into()),
+                )
+                .with_prompt("Repository Path")
+                .interact()
+                .map(|p| Cow::Owned(PathBuf::from(p)))
+                .map_err(RequestError::PathError)?,
+        };
+
+        let _ = find_repo(&repo_path)?;
+
+        let language = match partial.language {
+            Some(language) => Cow::Borrowed(language),
+            None => Cow::Owned(
+                Input::<String>::new()
+                    .with_prompt("Language (BCP 47 tag, such as `se` or `nb-NO`)")
+                    .interact()
+                    .map_err(|_| RequestError::InvalidInput)?,
+            ),
+        };
+
+        if !is_valid_lang_tag(&language) {
+            return Err(RequestError::InvalidLanguage(language.into_owned()));
+        }
+
+        let tags = partial.tags.unwrap_or(&[]);
+        let channels = partial.channels.unwrap_or(&[]);
+
+        // Nothing to change was given, so ask for it
+        let (tags, channels) = if tags.is_empty() && channels.is_empty() {
+            (
+                Cow::Owned(prompt_labels("Tag")?),
+                Cow::Owned(prompt_labels("Channel")?),
+            )
+        } else {
+            (Cow::Borrowed(tags), Cow::Borrowed(channels))
+        };
+
+        Ok(Request {
+            repo_path,
+            language,
+            tags,
+            channels,
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Failed to read file `{0}`")]
+    ReadFailed(PathBuf, #[source] io::Error),
+
+    #[error("Failed to read TOML file `{0}`")]
+    ReadToml(PathBuf, #[source] toml::de::Error),
+
+    #[error("Failed to create directory `{0}`")]
+    DirCreateFailed(PathBuf, #[source] io::Error),
+
+    #[error("Failed to write TOML file `{0}`")]
+    WriteToml(PathBuf, #[source] io::Error),
+
+    #[error("Failed to serialize TOML for `{0}`")]
+    SerializeToml(PathBuf, #[source] toml::ser::Error),
+
+    #[error("Could not find repository at provided path")]
+    NoRepo(#[from] FindRepoError),
+}
+
+fn apply_labels(map: &mut BTreeMap<String, String>, labels: &[(String, String)]) {
+    for (key, label) in labels {
+        if label.is_empty() {
+            map.remove(key);
+        } else {
+            map.insert(key.to_string(), label.to_string());
+        }
+    }
+}
+
+pub fn strings<'a>(request: Request<'a>) -> Result<(), Error> {
+    log::debug!("{:?}", request);
+
+    let strings_dir = find_repo(&request.repo_path)?.join("strings");
+    let strings_path = strings_dir.join(format!("{}.toml", request.language));
+
+    let mut strings: Localisation = match fs::read_to_string(&strings_path) {
+        Ok(v) => toml::from_str(&v).map_err(|e| Error::ReadToml(strings_path.clone(), e))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Localisation::default(),
+        Err(e) => return Err(Error::ReadFailed(strings_path, e)),
+    };
+
+    apply_labels(&mut strings.tags, &request.tags);
+    apply_labels(&mut strings.channels, &request.channels);
+
+    create_dir_all(&strings_dir).map_err(|e| Error::DirCreateFailed(strings_dir.clone(), e))?;
+
+    let data =
+        toml::to_string(&strings).map_err(|e| Error::SerializeToml(strings_path.clone(), e))?;
+    fs::write(&strings_path, data).map_err(|e| Error::WriteToml(strings_path.to_path_buf(), e))?;
+
+    Ok(())
+}
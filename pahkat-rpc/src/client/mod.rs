@@ -117,7 +117,10 @@ pub async fn run() -> anyhow::Result<()> {
 
             let req = stream::iter(vec![pb::TransactionRequest {
                 value: Some(pb::transaction_request::Value::Transaction(
-                    pb::transaction_request::Transaction { actions },
+                    pb::transaction_request::Transaction {
+                        actions,
+                        languages: vec![],
+                    },
                 )),
             }]);
 
@@ -387,6 +390,57 @@ pub extern "C" fn pahkat_rpc_resolve_package_query(
     serde_json::from_str(&response?.json).box_err()
 }
 
+#[cthulhu::invoke(return_marshaler = "JsonMarshaler")]
+pub extern "C" fn pahkat_rpc_plan_transaction(
+    #[marshal(cursed::ArcRefMarshaler::<RwLock<PahkatClient>>)] client: Arc<RwLock<PahkatClient>>,
+    #[marshal(cursed::StrMarshaler::<'_>)] actions: &str,
+) -> Result<pahkat_client::transaction::plan::TransactionPlan, Box<dyn Error>> {
+    let request = Request::new(pb::JsonRequest {
+        json: actions.to_string(),
+    });
+
+    let response: Result<pb::JsonResponse, Box<dyn Error>> = block_on(async move {
+        let mut client = client.write().await;
+        let response = client.plan_transaction(request).await.box_err()?;
+        Ok(response.into_inner())
+    });
+
+    serde_json::from_str(&response?.json).box_err()
+}
+
+#[cthulhu::invoke(return_marshaler = "JsonMarshaler")]
+pub extern "C" fn pahkat_rpc_list_updates(
+    #[marshal(cursed::ArcRefMarshaler::<RwLock<PahkatClient>>)] client: Arc<RwLock<PahkatClient>>,
+) -> Result<Vec<pahkat_client::package_store::PackageUpdate>, Box<dyn Error>> {
+    let request = Request::new(pb::ListUpdatesRequest {});
+
+    let response: Result<pb::JsonResponse, Box<dyn Error>> = block_on(async move {
+        let mut client = client.write().await;
+        let response = client.list_updates(request).await.box_err()?;
+        Ok(response.into_inner())
+    });
+
+    serde_json::from_str(&response?.json).box_err()
+}
+
+#[cthulhu::invoke(return_marshaler = "JsonMarshaler")]
+pub extern "C" fn pahkat_rpc_history(
+    #[marshal(cursed::ArcRefMarshaler::<RwLock<PahkatClient>>)] client: Arc<RwLock<PahkatClient>>,
+    #[marshal(cursed::StrMarshaler::<'_>)] package_keys: &str,
+) -> Result<Vec<pahkat_client::transaction::history::HistoryEntry>, Box<dyn Error>> {
+    let request = Request::new(pb::JsonRequest {
+        json: package_keys.to_string(),
+    });
+
+    let response: Result<pb::JsonResponse, Box<dyn Error>> = block_on(async move {
+        let mut client = client.write().await;
+        let response = client.history(request).await.box_err()?;
+        Ok(response.into_inner())
+    });
+
+    serde_json::from_str(&response?.json).box_err()
+}
+
 #[cthulhu::invoke(return_marshaler = "cursed::UnitMarshaler")]
 pub extern "C" fn pahkat_rpc_process_transaction(
     #[marshal(cursed::ArcRefMarshaler::<RwLock<PahkatClient>>)] client: Arc<RwLock<PahkatClient>>,
@@ -425,7 +479,10 @@ pub extern "C" fn pahkat_rpc_process_transaction(
 
     tx.send(pb::TransactionRequest {
         value: Some(pb::transaction_request::Value::Transaction(
-            pb::transaction_request::Transaction { actions },
+            pb::transaction_request::Transaction {
+                actions,
+                languages: vec![],
+            },
         )),
     })?;
 
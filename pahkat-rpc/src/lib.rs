@@ -6,8 +6,9 @@ pub mod server;
 use futures::stream::{StreamExt, TryStreamExt};
 use log::{error, info, warn};
 use pahkat_client::{
-    config::RepoRecord, package_store::InstallTarget, PackageAction, PackageActionType, PackageKey,
-    PackageStatus, PackageStore, PackageTransaction,
+    config::RepoRecord, package_store::InstallTarget, repo::RepoMove,
+    transaction::history::Initiator, CancellationToken, PackageAction, PackageActionType,
+    PackageKey, PackageStore, PackageTransaction,
 };
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
@@ -62,13 +63,21 @@ impl From<PackageAction> for pb::PackageAction {
     }
 }
 
-impl From<pahkat_client::transaction::ResolvedAction> for pb::ResolvedAction {
-    fn from(record: pahkat_client::transaction::ResolvedAction) -> Self {
-        pb::ResolvedAction {
-            action: Some(record.action.into()),
-            name: record.descriptor.name.into_iter().collect(),
-            version: record.release.version.to_string(),
-        }
+/// Converts an action, picking its display name from the preferred `languages`.
+fn resolved_action(
+    record: pahkat_client::transaction::ResolvedAction,
+    languages: &[String],
+) -> pb::ResolvedAction {
+    pb::ResolvedAction {
+        action: Some(record.action.into()),
+        display_name: record
+            .descriptor
+            .name
+            .lookup(languages)
+            .cloned()
+            .unwrap_or_else(|| record.descriptor.package.id.clone()),
+        name: record.descriptor.name.into_iter().collect(),
+        version: record.release.version.to_string(),
     }
 }
 
@@ -135,6 +144,15 @@ enum Notification {
     RpcStopping,
     TransactionLocked,
     TransactionUnlocked,
+    TransactionInterrupted,
+    RepositoryMoved(RepoMove),
+}
+
+/// Tells subscribers about the repositories the last refresh found to have moved.
+fn notify_moves(store: &dyn PackageStore, notifications: &broadcast::Sender<Notification>) {
+    for m in store.moves().read().unwrap().iter() {
+        let _ = notifications.send(Notification::RepositoryMoved(m.clone()));
+    }
 }
 
 type Result<T> = std::result::Result<Response<T>, Status>;
@@ -160,6 +178,14 @@ impl pb::pahkat_server::Pahkat for Rpc {
         let mut rx = self.notifications.subscribe();
         let current_transaction = Arc::clone(&self.current_transaction);
         let requires_reboot = self.requires_reboot.load(std::sync::atomic::Ordering::SeqCst);
+        let is_interrupted = match self.store.interrupted_transaction() {
+            Ok(v) => v.is_some(),
+            Err(e) => {
+                log::error!("{:?}", e);
+                false
+            }
+        };
+        let moves = self.store.moves().read().unwrap().clone();
 
         // log::info!("Peer: {:?}", _request.peer_cred());
 
@@ -167,11 +193,23 @@ impl pb::pahkat_server::Pahkat for Rpc {
             use pb::notification_response::ValueType;
             // Do the initial checks
             if requires_reboot {
-                yield pb::NotificationResponse { value: ValueType::RebootRequired as i32 };
+                yield pb::NotificationResponse { value: ValueType::RebootRequired as i32, ..Default::default() };
             }
 
             if current_transaction.try_lock().is_err() {
-                yield pb::NotificationResponse { value: ValueType::TransactionLocked as i32 };
+                yield pb::NotificationResponse { value: ValueType::TransactionLocked as i32, ..Default::default() };
+            }
+
+            if is_interrupted {
+                yield pb::NotificationResponse { value: ValueType::TransactionInterrupted as i32, ..Default::default() };
+            }
+
+            for m in moves {
+                yield pb::NotificationResponse {
+                    value: ValueType::RepositoryMoved as i32,
+                    from_url: m.from.to_string(),
+                    to_url: m.to.to_string(),
+                };
             }
 
             while let response = rx.recv().await {
@@ -179,20 +217,30 @@ impl pb::pahkat_server::Pahkat for Rpc {
                     Ok(response) => {
                         match response {
                             Notification::RebootRequired => {
-                                yield pb::NotificationResponse { value: ValueType::RebootRequired as i32 };
+                                yield pb::NotificationResponse { value: ValueType::RebootRequired as i32, ..Default::default() };
                             }
                             Notification::RepositoriesChanged => {
-                                yield pb::NotificationResponse { value: ValueType::RepositoriesChanged as i32 };
+                                yield pb::NotificationResponse { value: ValueType::RepositoriesChanged as i32, ..Default::default() };
                             }
                             Notification::RpcStopping => {
-                                yield pb::NotificationResponse { value: ValueType::RpcStopping as i32 };
+                                yield pb::NotificationResponse { value: ValueType::RpcStopping as i32, ..Default::default() };
                                 break;
                             }
                             Notification::TransactionLocked => {
-                                yield pb::NotificationResponse { value: ValueType::TransactionLocked as i32 };
+                                yield pb::NotificationResponse { value: ValueType::TransactionLocked as i32, ..Default::default() };
                             }
                             Notification::TransactionUnlocked => {
-                                yield pb::NotificationResponse { value: ValueType::TransactionUnlocked as i32 };
+                                yield pb::NotificationResponse { value: ValueType::TransactionUnlocked as i32, ..Default::default() };
+                            }
+                            Notification::TransactionInterrupted => {
+                                yield pb::NotificationResponse { value: ValueType::TransactionInterrupted as i32, ..Default::default() };
+                            }
+                            Notification::RepositoryMoved(m) => {
+                                yield pb::NotificationResponse {
+                                    value: ValueType::RepositoryMoved as i32,
+                                    from_url: m.from.to_string(),
+                                    to_url: m.to.to_string(),
+                                };
                             }
                         }
                     },
@@ -272,6 +320,8 @@ impl pb::pahkat_server::Pahkat for Rpc {
             let mut has_requested = false;
             let mut has_cancelled = false;
             let mut requires_reboot = false;
+            let mut cancel: Option<CancellationToken> = None;
+            let mut languages: Vec<String> = vec![];
 
             futures::pin_mut!(request);
             let (escape_catch_tx, _) = tokio::sync::broadcast::channel(1);
@@ -297,7 +347,7 @@ impl pb::pahkat_server::Pahkat for Rpc {
                     Ok(None) => return,
                 };
 
-                let request = match value {
+                let transaction = match value {
                     pb::transaction_request::Value::Transaction(v) => {
                         if has_requested {
                             // Duplicate transaction requests on same pipe is an error.
@@ -305,33 +355,81 @@ impl pb::pahkat_server::Pahkat for Rpc {
                             return;
                         }
                         has_requested = true;
-                        v
+
+                        let actions = v
+                            .actions
+                            .into_iter()
+                            .map(|x| PackageAction::from(x))
+                            .collect::<Vec<_>>();
+                        println!("{:?}", &actions);
+                        languages = v.languages;
+
+                        PackageTransaction::new(Arc::clone(&store) as _, actions)
+                            .map_err(|e| format!("{}", e))
                     }
-                    pb::transaction_request::Value::Cancel(_) => {
+                    pb::transaction_request::Value::Recover(v) => {
                         if has_requested {
-                            // We can cancel this transaction as it is ours.
-                            has_cancelled = true;
+                            return;
                         }
+                        has_requested = true;
 
-                        return;
+                        use pb::transaction_request::recover::Mode;
+
+                        let transaction = match Mode::from_i32(v.mode) {
+                            Some(Mode::Resume) => PackageTransaction::resume(Arc::clone(&store) as _),
+                            Some(Mode::Rollback) => {
+                                PackageTransaction::rollback(Arc::clone(&store) as _)
+                            }
+                            None => {
+                                // Let the client retry with a valid mode.
+                                has_requested = false;
+                                let response = pb::TransactionResponse {
+                                    value: Some(pb::transaction_response::Value::TransactionError(
+                                        pb::transaction_response::TransactionError {
+                                            package_id: "".to_string(),
+                                            error: format!("Invalid recovery mode: {}", v.mode),
+                                        },
+                                    )),
+                                };
+                                if let Err(err) = tx.send(Ok(response)) {
+                                    log::error!("{:?}", err);
+                                }
+                                continue 'listener;
+                            }
+                        };
+                        transaction.map_err(|e| format!("{}", e))
+                    }
+                    pb::transaction_request::Value::Cancel(_) => {
+                        if !has_requested {
+                            return;
+                        }
+
+                        // We can cancel this transaction as it is ours. Keep listening so the
+                        // transaction stream can wind down and release the lock.
+                        if let Some(cancel) = cancel.as_ref() {
+                            cancel.cancel();
+                        }
+                        has_cancelled = true;
+                        continue 'listener;
                     }
                 };
 
-                let actions = request
-                    .actions
-                    .into_iter()
-                    .map(|x| PackageAction::from(x))
-                    .collect::<Vec<_>>();
-                println!("{:?}", &actions);
+                // Fail before anything is downloaded if there is not enough space.
+                let transaction = transaction.and_then(|transaction| {
+                    match transaction.plan().and_then(|plan| plan.validate()) {
+                        Ok(_) => Ok(transaction.with_initiator(Initiator::Rpc)),
+                        Err(e) => Err(format!("{}", e)),
+                    }
+                });
 
-                let transaction = match PackageTransaction::new(Arc::clone(&store) as _, actions) {
+                let transaction = match transaction {
                     Ok(v) => v,
-                    Err(e) => {
+                    Err(error) => {
                         let response = pb::TransactionResponse {
                             value: Some(pb::transaction_response::Value::TransactionError(
                                 pb::transaction_response::TransactionError {
                                     package_id: "".to_string(),
-                                    error: format!("{}", e),
+                                    error,
                                 },
                             )),
                         };
@@ -345,11 +443,14 @@ impl pb::pahkat_server::Pahkat for Rpc {
                     }
                 };
 
+                cancel = Some(transaction.cancellation_token());
+
                 let store = Arc::clone(&store);
                 let current_transaction = Arc::clone(&current_transaction);
 
                 let tx = tx.clone();
                 let notifications = notifications.clone();
+                let languages = languages.clone();
 
                 tokio::spawn(async move {
                     // If there is a running transaction, we must block on this transaction and wait
@@ -381,7 +482,7 @@ impl pb::pahkat_server::Pahkat for Rpc {
 
                         yield pb::TransactionResponse {
                             value: Some(Value::TransactionStarted(TransactionStarted {
-                                actions: transaction.actions().iter().cloned().map(|x| x.into()).collect(),
+                                actions: transaction.actions().iter().cloned().map(|x| resolved_action(x, &languages)).collect(),
                                 is_reboot_required: requires_reboot,
                             }))
                         };
@@ -394,9 +495,7 @@ impl pb::pahkat_server::Pahkat for Rpc {
                             }
                             
                             let id = record.action.id.clone();
-                            let mut download = store.download(&record.action.id);
-
-                            // TODO: handle cancel here
+                            let mut download = store.download(&record.action.id, transaction.cancellation_token());
 
                             while let Some(event) = download.next().await {
                                 match event {
@@ -430,14 +529,12 @@ impl pb::pahkat_server::Pahkat for Rpc {
                         }
                         log::trace!("Ending download stream");
 
-                        let (canceler, mut tx_stream) = transaction.process();
+                        let (_cancel, mut tx_stream) = transaction.process();
                         let mut is_completed = false;
 
                         while let Some(event) = tx_stream.next().await {
                             use pahkat_client::transaction::TransactionEvent;
 
-                            // TODO: handle cancel here
-
                             match event {
                                 TransactionEvent::Installing(id) => {
                                     yield pb::TransactionResponse {
@@ -453,13 +550,13 @@ impl pb::pahkat_server::Pahkat for Rpc {
                                         }))
                                     };
                                 }
-                                TransactionEvent::Progress(id, msg) => {
+                                TransactionEvent::Progress(id, (current, total), msg) => {
                                     yield pb::TransactionResponse {
                                         value: Some(Value::TransactionProgress(TransactionProgress {
                                             package_id: id.to_string(),
                                             message: msg,
-                                            current: 0,
-                                            total: 0,
+                                            current,
+                                            total,
                                         }))
                                     };
                                 }
@@ -523,6 +620,10 @@ impl pb::pahkat_server::Pahkat for Rpc {
 
             let _ = notifications.send(Notification::TransactionUnlocked);
 
+            if let Ok(Some(_)) = store.interrupted_transaction() {
+                let _ = notifications.send(Notification::TransactionInterrupted);
+            }
+
             if requires_reboot {
                 let _ = notifications.send(Notification::RebootRequired);
             }
@@ -574,6 +675,7 @@ impl pb::pahkat_server::Pahkat for Rpc {
         };
 
         let _ = self.notifications.send(Notification::RepositoriesChanged);
+        notify_moves(&*self.store, &self.notifications);
 
         let mut config = config.read().unwrap();
         let mut repos = config.repos();
@@ -643,6 +745,7 @@ impl pb::pahkat_server::Pahkat for Rpc {
         };
 
         let _ = self.notifications.send(Notification::RepositoriesChanged);
+        notify_moves(&*self.store, &self.notifications);
 
         let mut config = config.read().unwrap();
         let mut repos = config.repos();
@@ -676,6 +779,63 @@ impl pb::pahkat_server::Pahkat for Rpc {
             json: serde_json::to_string(&results).unwrap(),
         }))
     }
+
+    async fn plan_transaction(
+        &self,
+        request: Request<pb::JsonRequest>,
+    ) -> Result<pb::JsonResponse> {
+        log::debug!("Received plan_transaction request: {:?}", &request);
+        let json = request.into_inner().json;
+        let actions: Vec<PackageAction> = serde_json::from_str(&json)
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+
+        let transaction = PackageTransaction::new(Arc::clone(&self.store) as _, actions)
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+        let plan = transaction
+            .plan()
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+
+        Ok(tonic::Response::new(pb::JsonResponse {
+            json: serde_json::to_string(&plan).unwrap(),
+        }))
+    }
+
+    async fn list_updates(
+        &self,
+        _request: Request<pb::ListUpdatesRequest>,
+    ) -> Result<pb::JsonResponse> {
+        let updates = self
+            .store
+            .updates()
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+
+        Ok(tonic::Response::new(pb::JsonResponse {
+            json: serde_json::to_string(&updates).unwrap(),
+        }))
+    }
+
+    async fn history(&self, request: Request<pb::JsonRequest>) -> Result<pb::JsonResponse> {
+        log::debug!("Received history request: {:?}", &request);
+        let json = request.into_inner().json;
+        let keys: Vec<PackageKey> = serde_json::from_str(&json)
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+        let keys = keys
+            .into_iter()
+            .map(|x| x.without_query_params())
+            .collect::<Vec<_>>();
+
+        let entries = self
+            .store
+            .history()
+            .map_err(|e| Status::failed_precondition(format!("{}", e)))?
+            .into_iter()
+            .filter(|x| keys.is_empty() || keys.contains(&x.key))
+            .collect::<Vec<_>>();
+
+        Ok(tonic::Response::new(pb::JsonResponse {
+            json: serde_json::to_string(&entries).unwrap(),
+        }))
+    }
 }
 
 use std::path::Path;
@@ -800,6 +960,7 @@ fn create_background_update_service(
 
             time::delay_for(Duration::from_secs(2)).await;
             let _ = store.refresh_repos().await;
+            notify_moves(&*store, &notifications);
 
             log::info!("Running self-update check…");
             match server::selfupdate::self_update().await {
@@ -814,34 +975,12 @@ fn create_background_update_service(
 
             log::info!("Running update check…");
 
-            // Currently installed packages:
-            log::debug!("Iterating through all known packages...");
-            let updates = {
-                let repos = store.repos();
-                let repos = repos.read().unwrap();
-
-                let mut updates = vec![];
-
-                for (url, repo) in repos.iter() {
-                    log::debug!("## Repo: {:?}", &url);
-                    let statuses = store.all_statuses(url, pahkat_client::InstallTarget::System);
-
-                    for (key, value) in statuses.into_iter() {
-                        log::debug!(" - {:?}: {:?}", &key, &value);
-                        if let Ok(PackageStatus::RequiresUpdate) = value {
-                            updates.push((
-                                pahkat_client::types::PackageKey {
-                                    repository_url: url.clone(),
-                                    id: key,
-                                    query: Default::default(),
-                                },
-                                pahkat_client::InstallTarget::System,
-                            ));
-                        }
-                    }
+            let updates = match store.updates() {
+                Ok(v) => v,
+                Err(e) => {
+                    log::error!("Update check failed: {:?}", e);
+                    continue;
                 }
-
-                updates
             };
 
             log::debug!("Proposed updates: {:?}", &updates);
@@ -853,7 +992,7 @@ fn create_background_update_service(
 
             let actions = updates
                 .into_iter()
-                .map(|(package_key, target)| PackageAction::install(package_key, target))
+                .map(|update| PackageAction::install(update.key, update.target))
                 .collect::<Vec<_>>();
 
             log::debug!("Waiting for transaction lock…");
@@ -861,15 +1000,20 @@ fn create_background_update_service(
             log::debug!("Transaction lock attained.");
             let _ = notifications.send(Notification::TransactionLocked);
 
-            let transaction = PackageTransaction::new(Arc::clone(&store) as _, actions).unwrap(); // .map_err(|e| Status::failed_precondition(format!("{}", e)))?;
+            // Fails while an interrupted transaction is waiting to be recovered
+            let transaction = match PackageTransaction::new(Arc::clone(&store) as _, actions) {
+                Ok(v) => v.with_initiator(Initiator::Updater),
+                Err(e) => {
+                    log::error!("Could not create update transaction: {}", e);
+                    continue 'main;
+                }
+            };
 
             for record in transaction.actions().iter() {
                 let action = &record.action;
                 // let tx = tx.clone();
                 // let id = action.id.clone();
-                let mut download = store.download(&action.id);
-
-                // TODO: handle cancel here
+                let mut download = store.download(&action.id, CancellationToken::new());
 
                 use pahkat_client::package_store::DownloadEvent;
 
@@ -903,7 +1047,7 @@ fn create_background_update_service(
                 }
             }
 
-            let (_canceler, mut stream) = transaction.process();
+            let (_cancel, mut stream) = transaction.process();
 
             futures::pin_mut!(stream);
 
@@ -2,8 +2,8 @@ use futures::stream::{StreamExt, TryStreamExt};
 use once_cell::sync::Lazy;
 use pahkat_client::package_store::DownloadEvent;
 use pahkat_client::{
-    config::RepoRecord, package_store::InstallTarget, PackageAction, PackageActionType, PackageKey,
-    PackageStatus, PackageStore, PackageTransaction,
+    config::RepoRecord, package_store::InstallTarget, CancellationToken, PackageAction,
+    PackageActionType, PackageKey, PackageStatus, PackageStore, PackageTransaction,
 };
 use std::convert::TryFrom;
 use std::error::Error;
@@ -61,6 +61,10 @@ pub(crate) fn requires_update(store: &dyn PackageStore) -> bool {
             }
             PackageStatus::RequiresUpdate => true,
             PackageStatus::UpToDate => false,
+            PackageStatus::NewerThanAvailable => {
+                log::warn!("Installed Pahkat Service is newer than the available release");
+                false
+            }
         },
         Err(err) => {
             log::error!("{:?}", err);
@@ -87,7 +91,12 @@ pub async fn install(store: &dyn PackageStore) -> Result<(), Box<dyn Error>> {
 
 #[cfg(feature = "launchd")]
 pub async fn install(store: &dyn PackageStore) -> Result<(), Box<dyn Error>> {
-    store.install(&UPDATER_KEY, InstallTarget::System)?;
+    store.install(
+        &UPDATER_KEY,
+        InstallTarget::System,
+        &CancellationToken::new(),
+        &|_, _| {},
+    )?;
 
     // Stop should trigger an immediate restart.
     std::process::Command::new("launchctl")
@@ -112,7 +121,7 @@ pub(crate) async fn self_update() -> Result<bool, Box<dyn Error>> {
 
         // If update is available, download it.
         log::debug!("Downloading self-update package...");
-        let mut stream = store.download(&UPDATER_KEY);
+        let mut stream = store.download(&UPDATER_KEY, CancellationToken::new());
 
         while let Some(result) = stream.next().await {
             match result {
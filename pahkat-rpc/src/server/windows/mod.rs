@@ -10,8 +10,8 @@ use std::{
 };
 
 use pahkat_client::{
-    config::RepoRecord, package_store::InstallTarget, Config, PackageAction, PackageActionType,
-    PackageKey, PackageStatus, PackageStore, PackageTransaction,
+    config::RepoRecord, package_store::InstallTarget, CancellationToken, Config, PackageAction,
+    PackageActionType, PackageKey, PackageStatus, PackageStore, PackageTransaction,
 };
 
 const SELF_UPDATE_TIMEOUT: u64 = 30;
@@ -96,7 +96,12 @@ pub(crate) async fn self_update(
     log::info!("Beginning update check");
 
     // Expect the package to be downloaded already
-    match store.install(&super::selfupdate::UPDATER_KEY, InstallTarget::System) {
+    match store.install(
+        &super::selfupdate::UPDATER_KEY,
+        InstallTarget::System,
+        &CancellationToken::new(),
+        &|_, _| {},
+    ) {
         Ok(_) => {
             log::info!("Self-updated successfully.");
         }
@@ -0,0 +1,212 @@
+//! Normalized CPU architecture names, and which architectures can run code built for others.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::platform::Platform;
+
+/// A CPU architecture a target is built for.
+///
+/// Names are parsed ignoring case, and the other names an architecture goes by on various
+/// platforms and toolchains are accepted, so `amd64` is [`Arch::X86_64`]. Any other name is
+/// kept as [`Arch::Other`], and only ever matches the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Arch {
+    X86_64,
+    I686,
+    Aarch64,
+    Arm,
+    PowerPC64,
+    PowerPC,
+    Mips64,
+    Mips,
+    Other(String),
+}
+
+/// Each known architecture with the other names it goes by.
+const ALIASES: &[(Arch, &[&str])] = &[
+    (Arch::X86_64, &["amd64", "x64", "x86-64", "intel64"]),
+    (Arch::I686, &["x86", "i386", "i486", "i586", "ia32"]),
+    (Arch::Aarch64, &["arm64", "armv8", "arm64-v8a"]),
+    (
+        Arch::Arm,
+        &["armv7", "armv7l", "armhf", "armv6", "armeabi-v7a"],
+    ),
+    (Arch::PowerPC64, &["ppc64"]),
+    (Arch::PowerPC, &["ppc"]),
+    (Arch::Mips64, &[]),
+    (Arch::Mips, &[]),
+];
+
+impl Arch {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Arch::X86_64 => "x86_64",
+            Arch::I686 => "i686",
+            Arch::Aarch64 => "aarch64",
+            Arch::Arm => "arm",
+            Arch::PowerPC64 => "powerpc64",
+            Arch::PowerPC => "powerpc",
+            Arch::Mips64 => "mips64",
+            Arch::Mips => "mips",
+            Arch::Other(name) => name,
+        }
+    }
+
+    /// Whether this is an architecture pahkat knows, rather than [`Arch::Other`].
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Arch::Other(_))
+    }
+
+    /// The architectures whose code runs on this one under `platform`, most preferred
+    /// first, starting with this one itself.
+    ///
+    /// Fallbacks are the older architectures a CPU executes natively, and those a platform
+    /// emulates, such as `x86_64` under Rosetta on macOS. macOS no longer runs 32-bit code.
+    pub fn compatible(&self, platform: &Platform) -> Vec<Arch> {
+        let fallbacks: &[Arch] = match (platform, self) {
+            (Platform::Windows, Arch::X86_64) | (Platform::Linux, Arch::X86_64) => &[Arch::I686],
+            (Platform::MacOS, Arch::Aarch64) => &[Arch::X86_64],
+            (Platform::Windows, Arch::Aarch64) => &[Arch::Arm, Arch::I686],
+            (_, Arch::Aarch64) => &[Arch::Arm],
+            (_, Arch::PowerPC64) => &[Arch::PowerPC],
+            (_, Arch::Mips64) => &[Arch::Mips],
+            _ => &[],
+        };
+
+        let mut result = vec![self.clone()];
+        result.extend_from_slice(fallbacks);
+        result
+    }
+
+    /// How well code built for `target` suits a system running this architecture under
+    /// `platform`: `Some(0)` for native code, higher for each less preferred fallback, and
+    /// `None` if it cannot run at all.
+    pub fn preference(&self, platform: &Platform, target: &Arch) -> Option<usize> {
+        self.compatible(platform).iter().position(|x| x == target)
+    }
+}
+
+impl FromStr for Arch {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let arch = ALIASES
+            .iter()
+            .find(|(arch, aliases)| {
+                arch.as_str().eq_ignore_ascii_case(s)
+                    || aliases.iter().any(|x| x.eq_ignore_ascii_case(s))
+            })
+            .map(|(arch, _)| arch.clone())
+            .unwrap_or_else(|| Arch::Other(s.to_string()));
+
+        Ok(arch)
+    }
+}
+
+impl From<&str> for Arch {
+    fn from(s: &str) -> Self {
+        match s.parse() {
+            Ok(v) => v,
+            Err(e) => match e {},
+        }
+    }
+}
+
+impl From<String> for Arch {
+    fn from(s: String) -> Self {
+        Arch::from(&*s)
+    }
+}
+
+impl fmt::Display for Arch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Arch {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Arch {
+    fn deserialize<D>(deserializer: D) -> Result<Arch, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Ok(Arch::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: &[Arch] = &[
+        Arch::X86_64,
+        Arch::I686,
+        Arch::Aarch64,
+        Arch::Arm,
+        Arch::PowerPC64,
+        Arch::PowerPC,
+        Arch::Mips64,
+        Arch::Mips,
+    ];
+
+    #[test]
+    fn round_trip() {
+        // The index stores architectures by these names, and clients parse them back
+        for arch in KNOWN {
+            assert!(arch.is_known());
+            assert_eq!(&Arch::from(arch.as_str()), arch);
+            assert_eq!(arch.to_string(), arch.as_str());
+
+            let json = serde_json::to_string(arch).unwrap();
+            assert_eq!(json, format!("\"{}\"", arch.as_str()));
+            assert_eq!(&serde_json::from_str::<Arch>(&json).unwrap(), arch);
+        }
+
+        assert_eq!(Arch::from("AMD64"), Arch::X86_64);
+        assert_eq!(Arch::from("arm64"), Arch::Aarch64);
+        assert_eq!(
+            serde_json::to_string(&Arch::from("x64")).unwrap(),
+            "\"x86_64\""
+        );
+    }
+
+    #[test]
+    fn unknown_values() {
+        let arch = Arch::from("sparc");
+        assert_eq!(arch, Arch::Other("sparc".into()));
+        assert!(!arch.is_known());
+        assert_eq!(arch.as_str(), "sparc");
+        assert_ne!(arch, Arch::from("SPARC"));
+
+        let arch: Arch = serde_json::from_str("\"riscv64\"").unwrap();
+        assert_eq!(arch, Arch::Other("riscv64".into()));
+        assert_eq!(serde_json::to_string(&arch).unwrap(), "\"riscv64\"");
+        assert_eq!(arch.compatible(&Platform::Linux), vec![arch.clone()]);
+    }
+
+    #[test]
+    fn fallbacks() {
+        let (windows, macos, linux) = (Platform::Windows, Platform::MacOS, Platform::Linux);
+        assert_eq!(Arch::X86_64.preference(&windows, &"x64".into()), Some(0));
+        assert_eq!(Arch::X86_64.preference(&windows, &"x86".into()), Some(1));
+        assert_eq!(Arch::I686.preference(&windows, &Arch::X86_64), None);
+        assert_eq!(Arch::X86_64.preference(&linux, &Arch::I686), Some(1));
+        assert_eq!(Arch::X86_64.preference(&macos, &Arch::I686), None);
+        assert_eq!(Arch::Aarch64.preference(&macos, &Arch::X86_64), Some(1));
+        assert_eq!(Arch::Aarch64.preference(&linux, &Arch::X86_64), None);
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+/// The language used when none of the preferred languages are available.
+pub const FALLBACK_LANG_TAG: &str = "en";
+
+/// Tags from before RFC 4646 that do not follow the current syntax, but are still well-formed.
+const GRANDFATHERED: &[&str] = &[
+    "en-GB-oed",
+    "i-ami",
+    "i-bnn",
+    "i-default",
+    "i-enochian",
+    "i-hak",
+    "i-klingon",
+    "i-lux",
+    "i-mingo",
+    "i-navajo",
+    "i-pwn",
+    "i-tao",
+    "i-tay",
+    "i-tsu",
+    "sgn-BE-FR",
+    "sgn-BE-NL",
+    "sgn-CH-DE",
+    "art-lojban",
+    "cel-gaulish",
+    "no-bok",
+    "no-nyn",
+    "zh-guoyu",
+    "zh-hakka",
+    "zh-min",
+    "zh-min-nan",
+    "zh-xiang",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid BCP 47 language tag: {0:?}")]
+pub struct LangTagError(pub String);
+
+/// Whether `tag` is a well-formed BCP 47 language tag, as defined by RFC 5646.
+///
+/// Only the syntax is checked; subtags are not looked up in the IANA registry.
+pub fn is_valid_lang_tag(tag: &str) -> bool {
+    if GRANDFATHERED.iter().any(|x| x.eq_ignore_ascii_case(tag)) {
+        return true;
+    }
+
+    let subtags = tag.split('-').collect::<Vec<_>>();
+    if subtags
+        .iter()
+        .any(|x| x.is_empty() || x.len() > 8 || !x.bytes().all(|b| b.is_ascii_alphanumeric()))
+    {
+        return false;
+    }
+
+    let is_alpha = |x: &str| x.bytes().all(|b| b.is_ascii_alphabetic());
+    let is_digit = |x: &str| x.bytes().all(|b| b.is_ascii_digit());
+    let is_private_use = |x: &str| x.eq_ignore_ascii_case("x");
+
+    // A tag made only of private use subtags
+    if is_private_use(subtags[0]) {
+        return subtags.len() > 1;
+    }
+
+    let mut rest = &subtags[..];
+    let mut next_if = |predicate: &dyn Fn(&str) -> bool| match rest.first() {
+        Some(x) if predicate(x) => {
+            rest = &rest[1..];
+            true
+        }
+        _ => false,
+    };
+
+    // language, with up to three extended language subtags after a short language code
+    let is_short_language = |x: &str| is_alpha(x) && (x.len() == 2 || x.len() == 3);
+    if next_if(&is_short_language) {
+        for _ in 0..3 {
+            if !next_if(&|x| is_alpha(x) && x.len() == 3) {
+                break;
+            }
+        }
+    } else if !next_if(&|x| is_alpha(x) && x.len() >= 4) {
+        return false;
+    }
+
+    // script
+    next_if(&|x| is_alpha(x) && x.len() == 4);
+
+    // region
+    next_if(&|x| (is_alpha(x) && x.len() == 2) || (is_digit(x) && x.len() == 3));
+
+    // variants
+    while next_if(&|x| x.len() >= 5 || (x.len() == 4 && x.as_bytes()[0].is_ascii_digit())) {}
+
+    // extensions, each a singleton followed by at least one subtag
+    while next_if(&|x| x.len() == 1 && !is_private_use(x)) {
+        if !next_if(&|x| x.len() >= 2) {
+            return false;
+        }
+        while next_if(&|x| x.len() >= 2) {}
+    }
+
+    // private use, with at least one subtag
+    if next_if(&is_private_use) {
+        if !next_if(&|_| true) {
+            return false;
+        }
+        while next_if(&|_| true) {}
+    }
+
+    rest.is_empty()
+}
+
+/// `tag` followed by each less specific tag made by removing subtags from the end, so
+/// `se-Latn-NO` gives `se-Latn-NO`, `se-Latn` and `se`.
+pub fn fallbacks(tag: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(tag), |tag| {
+        let mut tag = &tag[..tag.rfind('-')?];
+
+        // A singleton cannot end a tag, so it goes with the subtag it introduced
+        if let Some(index) = tag.rfind('-') {
+            if tag.len() - index == 2 {
+                tag = &tag[..index];
+            }
+        }
+
+        Some(tag)
+    })
+}
+
+/// A map keyed by BCP 47 language tags, such as the localised names of a package.
+///
+/// Keys are validated on insertion and when deserializing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct LangTagMap<T>(BTreeMap<String, T>);
+
+impl<T> LangTagMap<T> {
+    pub fn new() -> LangTagMap<T> {
+        LangTagMap(BTreeMap::new())
+    }
+
+    pub fn insert(&mut self, tag: String, value: T) -> Result<Option<T>, LangTagError> {
+        if !is_valid_lang_tag(&tag) {
+            return Err(LangTagError(tag));
+        }
+
+        Ok(self.0.insert(tag, value))
+    }
+
+    pub fn remove(&mut self, tag: &str) -> Option<T> {
+        self.0.remove(tag)
+    }
+
+    /// The value best matching the preferred languages, in order of preference.
+    ///
+    /// Each preferred tag is tried with subtags removed from the end until one matches,
+    /// so `se-NO` falls back to `se`. If none match, [`FALLBACK_LANG_TAG`] is tried, and
+    /// then whichever value comes first.
+    pub fn lookup<S: AsRef<str>>(&self, preferred: &[S]) -> Option<&T> {
+        preferred
+            .iter()
+            .map(AsRef::as_ref)
+            .chain(std::iter::once(FALLBACK_LANG_TAG))
+            .find_map(|tag| self.lookup_tag(tag))
+            .or_else(|| self.0.values().next())
+    }
+
+    fn lookup_tag(&self, tag: &str) -> Option<&T> {
+        fallbacks(tag).find_map(|x| self.get_ignore_case(x))
+    }
+
+    fn get_ignore_case(&self, tag: &str) -> Option<&T> {
+        self.0
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(tag))
+            .map(|(_, value)| value)
+    }
+
+    pub fn into_inner(self) -> BTreeMap<String, T> {
+        self.0
+    }
+}
+
+impl<T> Default for LangTagMap<T> {
+    fn default() -> Self {
+        LangTagMap::new()
+    }
+}
+
+impl<T> std::ops::Deref for LangTagMap<T> {
+    type Target = BTreeMap<String, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> std::convert::TryFrom<BTreeMap<String, T>> for LangTagMap<T> {
+    type Error = LangTagError;
+
+    fn try_from(map: BTreeMap<String, T>) -> Result<Self, Self::Error> {
+        if let Some(tag) = map.keys().find(|x| !is_valid_lang_tag(x)) {
+            return Err(LangTagError(tag.to_string()));
+        }
+
+        Ok(LangTagMap(map))
+    }
+}
+
+impl<T> IntoIterator for LangTagMap<T> {
+    type Item = (String, T);
+    type IntoIter = std::collections::btree_map::IntoIter<String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LangTagMap<T> {
+    type Item = (&'a String, &'a T);
+    type IntoIter = std::collections::btree_map::Iter<'a, String, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for LangTagMap<T> {
+    fn deserialize<D>(deserializer: D) -> Result<LangTagMap<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use std::convert::TryFrom;
+
+        let map = BTreeMap::<String, T>::deserialize(deserializer)?;
+        LangTagMap::try_from(map).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_lang_tags() {
+        for tag in &[
+            "en",
+            "se-NO",
+            "sma-Latn-SE",
+            "zh-yue-HK",
+            "sl-rozaj-biske",
+            "de-CH-1901",
+            "en-a-bbb-x-a-ccc",
+            "x-whatever",
+            "i-klingon",
+            "es-419",
+        ] {
+            assert!(is_valid_lang_tag(tag), "{}", tag);
+        }
+
+        for tag in &[
+            "",
+            "e",
+            "en_US",
+            "en-",
+            "-en",
+            "en-a",
+            "en-x",
+            "toolonglang",
+            "1234",
+        ] {
+            assert!(!is_valid_lang_tag(tag), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn lookup_falls_back() {
+        let mut map = LangTagMap::new();
+        map.insert("en".to_string(), "English").unwrap();
+        map.insert("se".to_string(), "Davvisámegiella").unwrap();
+        map.insert("nb".to_string(), "Norsk bokmål").unwrap();
+
+        assert_eq!(map.lookup(&["se-NO"]), Some(&"Davvisámegiella"));
+        assert_eq!(map.lookup(&["sma-NO", "nb-NO"]), Some(&"Norsk bokmål"));
+        assert_eq!(map.lookup(&["fi"]), Some(&"English"));
+        assert!(map.insert("en_US".to_string(), "English").is_err());
+        assert!(toml::from_str::<LangTagMap<String>>("nb_NO = \"Norsk\"").is_err());
+        assert_eq!(
+            fallbacks("en-a-bbb-x-ccc").collect::<Vec<_>>(),
+            vec!["en-a-bbb-x-ccc", "en-a-bbb", "en"]
+        );
+    }
+}
@@ -1,19 +1,20 @@
+pub mod arch;
+pub mod lang_tag;
 pub mod package;
 pub mod payload;
+pub mod platform;
 pub mod repo;
 pub mod synth;
 pub mod package_key;
 
-/// Will be replaced with a validating Map in the future.
-///
-/// Keys must be valid BCP 47 language tags.
-pub type LangTagMap<T> = std::collections::BTreeMap<String, T>;
-
 /// Will be replaced with a validating Map in the future.
 pub type DependencyMap = std::collections::BTreeMap<String, String>;
 
+pub use arch::Arch;
+pub use lang_tag::LangTagMap;
 pub use payload::AsDownloadUrl;
 pub use package_key::PackageKey;
+pub use platform::Platform;
 
 #[cfg(test)]
 mod tests {
@@ -22,9 +23,9 @@ mod tests {
     #[test]
     fn smoke() {
         let mut names = LangTagMap::new();
-        names.insert("en".to_string(), "Test Package".to_string());
+        names.insert("en".to_string(), "Test Package".to_string()).unwrap();
         let mut desc = LangTagMap::new();
-        desc.insert("en".to_string(), "A test package for testing.".to_string());
+        desc.insert("en".to_string(), "A test package for testing.".to_string()).unwrap();
 
         let mut deps = DependencyMap::new();
         deps.insert("some-dependency".to_string(), "*".to_string());
@@ -46,7 +47,7 @@ mod tests {
                     .license(Some("CC-1.0".to_string()))
                     .target(vec![
                         payload::Target::builder()
-                            .platform("windows".to_string())
+                            .platform("windows".into())
                             .arch(Some("x86_64".into()))
                             .dependencies(deps.clone())
                             .payload(payload::Payload::WindowsExecutable(
@@ -98,7 +99,7 @@ mod tests {
                     .license(Some("CC-1.0".to_string()))
                     .target(vec![
                         payload::Target::builder()
-                            .platform("windows".to_string())
+                            .platform("windows".into())
                             .arch(Some("x86_64".into()))
                             .dependencies(deps.clone())
                             .payload(payload::Payload::WindowsExecutable(
@@ -141,12 +142,15 @@ mod tests {
             .build();
 
         let mut names = LangTagMap::new();
-        names.insert("en".to_string(), "Another Package".to_string());
+        names
+            .insert("en".to_string(), "Another Package".to_string())
+            .unwrap();
         let mut desc = LangTagMap::new();
         desc.insert(
             "en".to_string(),
             "A second test package for testing.".to_string(),
-        );
+        )
+        .unwrap();
 
         let mut deps = DependencyMap::new();
         deps.insert("some-other-dependency".to_string(), "*".to_string());
@@ -168,7 +172,7 @@ mod tests {
                     .license(Some("CC-1.0".to_string()))
                     .target(vec![
                         payload::Target::builder()
-                            .platform("windows".to_string())
+                            .platform("windows".into())
                             .arch(Some("x86_64".into()))
                             .dependencies(deps.clone())
                             .payload(payload::Payload::WindowsExecutable(
@@ -214,7 +218,7 @@ mod tests {
                     .license(Some("CC-1.0".to_string()))
                     .target(vec![
                         payload::Target::builder()
-                            .platform("windows".to_string())
+                            .platform("windows".into())
                             .arch(Some("x86_64".into()))
                             .dependencies(deps.clone())
                             .payload(payload::Payload::WindowsExecutable(
@@ -264,6 +268,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn synthetic_file_ref() {
+        let toml = r#"
+            [synthetic]
+            id = "python"
+
+            [[releases]]
+            version = "3.8.2"
+            channel = ""
+
+            [[releases.targets]]
+            platform = "linux"
+
+            [releases.targets.verifier]
+            _type = "FileRef"
+            path = "/usr/bin/python3.8"
+            version_file = "share/python/VERSION"
+        "#;
+
+        let package: package::Package = toml::from_str(toml).unwrap();
+        let descriptor = match package {
+            package::Package::Synthetic(v) => v,
+            _ => panic!("Not a synthetic package"),
+        };
+
+        match &descriptor.releases[0].targets[0].verifier {
+            synth::Verifier::FileRef(v) => {
+                assert_eq!(v.path, "/usr/bin/python3.8");
+                assert_eq!(v.version_file.as_deref(), Some("share/python/VERSION"));
+            }
+            v => panic!("Wrong verifier: {:?}", v),
+        }
+    }
+
+    #[test]
+    fn target_platform_and_arch() {
+        let toml = r#"
+            [synthetic]
+            id = "python"
+
+            [[releases]]
+            version = "3.8.2"
+            channel = ""
+
+            [[releases.targets]]
+            platform = "macOS"
+            arch = "arm64"
+
+            [releases.targets.verifier]
+            _type = "FileRef"
+            path = "/usr/bin/python3.8"
+
+            [[releases.targets]]
+            platform = "haiku"
+            arch = "sparc"
+
+            [releases.targets.verifier]
+            _type = "FileRef"
+            path = "/bin/python3.8"
+        "#;
+
+        let package: package::Package = toml::from_str(toml).unwrap();
+        let descriptor = match &package {
+            package::Package::Synthetic(v) => v,
+            _ => panic!("Not a synthetic package"),
+        };
+
+        let targets = &descriptor.releases[0].targets;
+        assert_eq!(targets[0].platform, Platform::MacOS);
+        assert_eq!(targets[0].arch, Some(Arch::Aarch64));
+        assert_eq!(targets[1].platform, Platform::Other("haiku".into()));
+        assert_eq!(targets[1].arch, Some(Arch::Other("sparc".into())));
+
+        // Known values are written back by their canonical names, unknown ones as given
+        let output = toml::to_string(&package).unwrap();
+        assert!(output.contains(r#"platform = "macos""#));
+        assert!(output.contains(r#"arch = "aarch64""#));
+        assert!(output.contains(r#"platform = "haiku""#));
+        assert!(output.contains(r#"arch = "sparc""#));
+    }
+
     #[test]
     fn smoke2() {
         use crate::package::Descriptor;
@@ -28,6 +28,16 @@ impl Package {
             Package::Redirect(d) => &d.redirect.id,
         }
     }
+
+    /// The localised names of the package. Redirects have none.
+    #[inline]
+    pub fn name(&self) -> Option<&LangTagMap<String>> {
+        match self {
+            Package::Concrete(d) => Some(&d.name),
+            Package::Synthetic(d) => Some(&d.name),
+            Package::Redirect(_) => None,
+        }
+    }
 }
 
 impl TryFrom<Package> for Descriptor {
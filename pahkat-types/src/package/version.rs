@@ -1,8 +1,10 @@
 use std::cmp::Ordering;
 use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::str::FromStr;
 
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
@@ -33,11 +35,115 @@ impl std::ops::Deref for SemanticVersion {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Hash, Eq)]
-#[serde(untagged)]
+/// A version made of any number of dot-separated numbers, such as `1.2.3.4`, as used
+/// by many Windows installers.
+///
+/// Missing trailing components count as zero, so `1.2` and `1.2.0.0` are equal.
+#[derive(Debug, Clone)]
+pub struct NumericVersion {
+    parts: Vec<u64>,
+    raw: String,
+}
+
+impl NumericVersion {
+    pub fn parts(&self) -> &[u64] {
+        &self.parts
+    }
+
+    /// The components without trailing zeros, which compare and hash the same for
+    /// equal versions.
+    fn significant_parts(&self) -> &[u64] {
+        significant_parts(&self.parts)
+    }
+}
+
+fn significant_parts(parts: &[u64]) -> &[u64] {
+    let len = parts.iter().rposition(|x| *x != 0).map_or(0, |i| i + 1);
+    &parts[..len]
+}
+
+fn cmp_parts(a: &[u64], b: &[u64]) -> Ordering {
+    let len = a.len().max(b.len());
+    for i in 0..len {
+        let x = a.get(i).copied().unwrap_or(0);
+        let y = b.get(i).copied().unwrap_or(0);
+        match x.cmp(&y) {
+            Ordering::Equal => continue,
+            v => return v,
+        }
+    }
+    Ordering::Equal
+}
+
+impl FromStr for NumericVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts = s
+            .split('.')
+            .map(|x| {
+                if x.is_empty() || !x.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                x.parse::<u64>().ok()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| Error::UnhandledInput(s.to_string()))?;
+
+        Ok(NumericVersion {
+            parts,
+            raw: s.to_string(),
+        })
+    }
+}
+
+/// A version that is a point in time, given as an RFC 3339 timestamp or a `YYYY-MM-DD` date.
+#[derive(Debug, Clone)]
+pub struct DateVersion {
+    date: DateTime<Utc>,
+    raw: String,
+}
+
+impl DateVersion {
+    pub fn date(&self) -> &DateTime<Utc> {
+        &self.date
+    }
+}
+
+impl FromStr for DateVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let date = match DateTime::parse_from_rfc3339(s) {
+            Ok(v) => v.with_timezone(&Utc),
+            Err(_) => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|x| x.and_hms_opt(0, 0, 0))
+                .map(|x| Utc.from_utc_datetime(&x))
+                .ok_or_else(|| Error::UnhandledInput(s.to_string()))?,
+        };
+
+        Ok(DateVersion {
+            date,
+            raw: s.to_string(),
+        })
+    }
+}
+
+/// A package version.
+///
+/// Versions are ordered within their own scheme. Semantic and numeric versions are also
+/// ordered against each other by their numeric components, where a semantic pre-release
+/// comes before the numeric version with the same components. Other combinations, and
+/// two different opaque versions, have no ordering and are only equal if identical.
+#[derive(Debug, Clone)]
 #[non_exhaustive]
 pub enum Version {
     Semantic(SemanticVersion),
+    Numeric(NumericVersion),
+    Date(DateVersion),
+    /// A version with no known structure, compared only for equality.
+    Opaque(String),
 }
 
 #[derive(Debug, Clone, Error)]
@@ -47,13 +153,52 @@ pub enum Error {
 }
 
 impl Version {
+    /// Parses a version, trying in order the semantic, date and numeric schemes before
+    /// falling back to an opaque version.
     pub fn new(version: &str) -> Result<Self, Error> {
-        match version.parse::<SemanticVersion>() {
-            Ok(v) => return Ok(Version::Semantic(v)),
-            Err(_) => { /* fall through */ }
+        if version.trim().is_empty() {
+            return Err(Error::UnhandledInput(version.to_string()));
+        }
+
+        if let Ok(v) = version.parse::<SemanticVersion>() {
+            return Ok(Version::Semantic(v));
+        }
+
+        if let Ok(v) = version.parse::<DateVersion>() {
+            return Ok(Version::Date(v));
         }
 
-        Err(Error::UnhandledInput(version.to_string()))
+        if let Ok(v) = version.parse::<NumericVersion>() {
+            return Ok(Version::Numeric(v));
+        }
+
+        Ok(Version::Opaque(version.to_string()))
+    }
+
+    /// Parses a version in the scheme given by a `version_type` code from a package index.
+    ///
+    /// Unknown codes fall back to detecting the scheme as `Version::new` does.
+    pub fn from_version_type(version_type: u8, version: &str) -> Result<Self, Error> {
+        match version_type {
+            1 => Ok(Version::Opaque(version.to_string())),
+            2 => version
+                .parse::<SemanticVersion>()
+                .map(Version::Semantic)
+                .map_err(|_| Error::UnhandledInput(version.to_string())),
+            3 => version.parse().map(Version::Numeric),
+            4 => version.parse().map(Version::Date),
+            _ => Version::new(version),
+        }
+    }
+
+    /// The `version_type` code of this version's scheme in a package index.
+    pub fn version_type(&self) -> u8 {
+        match self {
+            Version::Opaque(_) => 1,
+            Version::Semantic(_) => 2,
+            Version::Numeric(_) => 3,
+            Version::Date(_) => 4,
+        }
     }
 }
 
@@ -61,6 +206,9 @@ impl Display for Version {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Version::Semantic(semver) => semver.0.fmt(f),
+            Version::Numeric(v) => f.write_str(&v.raw),
+            Version::Date(v) => f.write_str(&v.raw),
+            Version::Opaque(v) => f.write_str(v),
         }
     }
 }
@@ -74,14 +222,53 @@ impl PartialEq for Version {
     }
 }
 
+impl Eq for Version {}
+
+impl Hash for Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Equal semantic and numeric versions must hash the same.
+        match self {
+            Version::Semantic(v) if v.pre.is_empty() => {
+                significant_parts(&[v.major, v.minor, v.patch]).hash(state)
+            }
+            Version::Semantic(v) => v.hash(state),
+            Version::Numeric(v) => v.significant_parts().hash(state),
+            Version::Date(v) => v.date.hash(state),
+            Version::Opaque(v) => v.hash(state),
+        }
+    }
+}
+
 impl PartialOrd for Version {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
             (Version::Semantic(my), Version::Semantic(other)) => Some(my.cmp(other)),
+            (Version::Numeric(my), Version::Numeric(other)) => {
+                Some(cmp_parts(&my.parts, &other.parts))
+            }
+            (Version::Semantic(my), Version::Numeric(other)) => {
+                Some(cmp_semantic_numeric(my, other))
+            }
+            (Version::Numeric(my), Version::Semantic(other)) => {
+                Some(cmp_semantic_numeric(other, my).reverse())
+            }
+            (Version::Date(my), Version::Date(other)) => Some(my.date.cmp(&other.date)),
+            (Version::Opaque(my), Version::Opaque(other)) if my == other => Some(Ordering::Equal),
+            _ => None,
         }
     }
 }
 
+fn cmp_semantic_numeric(semantic: &SemanticVersion, numeric: &NumericVersion) -> Ordering {
+    match cmp_parts(
+        &[semantic.major, semantic.minor, semantic.patch],
+        &numeric.parts,
+    ) {
+        Ordering::Equal if !semantic.pre.is_empty() => Ordering::Less,
+        v => v,
+    }
+}
+
 impl FromStr for Version {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -89,6 +276,19 @@ impl FromStr for Version {
     }
 }
 
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Version::new(&value).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -145,4 +345,68 @@ mod tests {
         assert_eq!(my.partial_cmp(&other), Some(Ordering::Greater));
         assert_ne!(my, other);
     }
+
+    #[test]
+    fn test_numeric_four_parts() {
+        let my = Version::new("1.2.3.4").unwrap();
+        let other = Version::new("1.2.3.10").unwrap();
+
+        assert!(matches!(my, Version::Numeric(_)));
+        assert_eq!(my.partial_cmp(&other), Some(Ordering::Less));
+        assert_eq!(my.to_string(), "1.2.3.4");
+    }
+
+    #[test]
+    fn test_numeric_trailing_zeros() {
+        let my = Version::new("1.2.0.0").unwrap();
+        let other = Version::new("1.2").unwrap();
+
+        assert_eq!(my, other);
+    }
+
+    #[test]
+    fn test_semver_against_numeric() {
+        let semver = Version::new("1.2.3").unwrap();
+        let pre = Version::new("1.2.3-beta.1").unwrap();
+        let numeric = Version::new("1.2.3.0").unwrap();
+        let newer = Version::new("1.2.3.1").unwrap();
+
+        assert_eq!(semver, numeric);
+        assert!(pre < numeric);
+        assert!(semver < newer);
+        assert!(newer > semver);
+    }
+
+    #[test]
+    fn test_dates() {
+        let my = Version::new("2019-01-01").unwrap();
+        let other = Version::new("2019-01-01T12:00:00Z").unwrap();
+
+        assert!(matches!(my, Version::Date(_)));
+        assert!(my < other);
+        assert_eq!(other.to_string(), "2019-01-01T12:00:00Z");
+    }
+
+    #[test]
+    fn test_opaque() {
+        let my = Version::new("r1234-win").unwrap();
+        let same = Version::new("r1234-win").unwrap();
+        let other = Version::new("r1235-win").unwrap();
+
+        assert!(matches!(my, Version::Opaque(_)));
+        assert_eq!(my, same);
+        assert_ne!(my, other);
+        assert_eq!(my.partial_cmp(&other), None);
+        assert_eq!(my.partial_cmp(&Version::new("1.0.0").unwrap()), None);
+    }
+
+    #[test]
+    fn test_version_type_round_trip() {
+        for input in &["1.2.3", "1.2.3.4", "2020-02-02", "nightly"] {
+            let version = Version::new(input).unwrap();
+            let parsed = Version::from_version_type(version.version_type(), input).unwrap();
+            assert_eq!(version, parsed);
+            assert_eq!(version.version_type(), parsed.version_type());
+        }
+    }
 }
@@ -5,14 +5,16 @@ use serde::de::{self, Deserialize, Deserializer, Visitor};
 use serde::ser::{Serialize, Serializer};
 use url::Url;
 
+use crate::arch::Arch;
+use crate::platform::Platform;
 use crate::repo::RepoUrl;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct PackageKeyParams {
     pub channel: Option<String>,
-    pub platform: Option<String>,
+    pub platform: Option<Platform>,
     pub version: Option<String>,
-    pub arch: Option<String>,
+    pub arch: Option<Arch>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -68,7 +70,7 @@ impl<'a> From<&'a PackageKey> for Url {
             let mut query = url.query_pairs_mut();
 
             if let Some(arch) = key.query.arch.as_ref() {
-                query.append_pair("arch", arch);
+                query.append_pair("arch", arch.as_str());
             }
 
             if let Some(channel) = key.query.channel.as_ref() {
@@ -76,7 +78,7 @@ impl<'a> From<&'a PackageKey> for Url {
             }
 
             if let Some(platform) = key.query.platform.as_ref() {
-                query.append_pair("platform", platform);
+                query.append_pair("platform", platform.as_str());
             }
 
             if let Some(version) = key.query.version.as_ref() {
@@ -135,8 +137,8 @@ impl<'a> TryFrom<&'a Url> for PackageKey {
             match &*k {
                 "version" => query.version = Some(v.to_string()),
                 "channel" => query.channel = Some(v.to_string()),
-                "platform" => query.platform = Some(v.to_string()),
-                "arch" => query.arch = Some(v.to_string()),
+                "platform" => query.platform = Some(Platform::from(&*v)),
+                "arch" => query.arch = Some(Arch::from(&*v)),
                 _ => {}
             }
         }
@@ -6,7 +6,7 @@ use std::collections::BTreeSet;
 use std::convert::TryFrom;
 use std::str::FromStr;
 
-use crate::DependencyMap;
+use crate::{Arch, DependencyMap, Platform};
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
@@ -45,6 +45,14 @@ impl Payload {
         }
     }
 
+    pub fn url(&self) -> &url::Url {
+        match self {
+            Payload::WindowsExecutable(x) => &x.url,
+            Payload::MacOSPackage(x) => &x.url,
+            Payload::TarballPackage(x) => &x.url,
+        }
+    }
+
     pub fn set_url(&mut self, url: url::Url) {
         match self {
             Payload::WindowsExecutable(x) => { x.url = url; },
@@ -58,13 +66,36 @@ impl Payload {
     Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, TypedBuilder,
 )]
 pub struct Target {
-    pub platform: String,
+    pub platform: Platform,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub arch: Option<Arch>,
+    /// The oldest OS version this target supports, such as `10.15` or `10.0.17763`. On
+    /// Linux, this is the `VERSION_ID` of the distribution, such as `22.04`.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     #[builder(default)]
-    pub arch: Option<String>,
+    pub min_os_version: Option<String>,
+    /// The newest OS version this target supports. Versions are compared only as far as
+    /// the bound is given, so `10.15` includes `10.15.7`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub max_os_version: Option<String>,
     #[serde(default)]
     #[builder(default)]
     pub dependencies: DependencyMap,
+    /// Packages, by identifier or package key, that cannot be installed alongside this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub conflicts: Vec<String>,
+    /// Virtual names this package satisfies when another package depends on them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub provides: Vec<String>,
+    /// Packages, by identifier or package key, that this package supersedes. Installing it
+    /// uninstalls them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    pub replaces: Vec<String>,
     pub payload: Payload,
 }
 
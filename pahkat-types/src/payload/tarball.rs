@@ -32,6 +32,79 @@ pub struct Package {
 
     #[cfg_attr(feature = "structopt", structopt(short, long))]
     pub installed_size: u64,
+
+    /// Script run from the unpacked package before it is moved into place
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(long))]
+    pub pre_install: Option<String>,
+
+    /// Script run once the package has been installed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(long))]
+    pub post_install: Option<String>,
+
+    /// Script run before any files of the package are removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(long))]
+    pub pre_uninstall: Option<String>,
+
+    /// Script run once the files of the package have been removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(long))]
+    pub post_uninstall: Option<String>,
+
+    /// Executables, relative to the package root, to expose in the prefix's `bin` directory
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(short = "e", long))]
+    pub executables: Vec<String>,
+
+    /// Seconds a hook script may run before it is stopped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    #[cfg_attr(feature = "structopt", structopt(long))]
+    pub hook_timeout: Option<u64>,
+}
+
+impl Package {
+    /// Returns the script, relative to the package root, declared for the given hook.
+    pub fn hook(&self, hook: Hook) -> Option<&str> {
+        match hook {
+            Hook::PreInstall => self.pre_install.as_deref(),
+            Hook::PostInstall => self.post_install.as_deref(),
+            Hook::PreUninstall => self.pre_uninstall.as_deref(),
+            Hook::PostUninstall => self.post_uninstall.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hook {
+    PreInstall,
+    PostInstall,
+    PreUninstall,
+    PostUninstall,
+}
+
+impl Hook {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Hook::PreInstall => "pre_install",
+            Hook::PostInstall => "post_install",
+            Hook::PreUninstall => "pre_uninstall",
+            Hook::PostUninstall => "post_uninstall",
+        }
+    }
+}
+
+impl std::fmt::Display for Hook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl super::AsDownloadUrl for Package {
@@ -0,0 +1,144 @@
+//! The operating systems packages are built for.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// The operating system a target is built for.
+///
+/// Names are parsed ignoring case, so `macOS` is [`Platform::MacOS`]. Any other name is kept
+/// as [`Platform::Other`], and only ever matches the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Platform {
+    Windows,
+    MacOS,
+    Ios,
+    Android,
+    Linux,
+    Other(String),
+}
+
+impl Platform {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Platform::Windows => "windows",
+            Platform::MacOS => "macos",
+            Platform::Ios => "ios",
+            Platform::Android => "android",
+            Platform::Linux => "linux",
+            Platform::Other(name) => name,
+        }
+    }
+
+    /// Whether this is a platform pahkat knows, rather than [`Platform::Other`].
+    pub fn is_known(&self) -> bool {
+        !matches!(self, Platform::Other(_))
+    }
+}
+
+impl FromStr for Platform {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let platform = match &*s.to_ascii_lowercase() {
+            "windows" | "win32" => Platform::Windows,
+            "macos" | "osx" | "macosx" | "darwin" => Platform::MacOS,
+            "ios" => Platform::Ios,
+            "android" => Platform::Android,
+            "linux" => Platform::Linux,
+            _ => Platform::Other(s.to_string()),
+        };
+
+        Ok(platform)
+    }
+}
+
+impl From<&str> for Platform {
+    fn from(s: &str) -> Self {
+        match s.parse() {
+            Ok(v) => v,
+            Err(e) => match e {},
+        }
+    }
+}
+
+impl From<String> for Platform {
+    fn from(s: String) -> Self {
+        Platform::from(&*s)
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for Platform {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Platform {
+    fn deserialize<D>(deserializer: D) -> Result<Platform, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Ok(Platform::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let known = [
+            Platform::Windows,
+            Platform::MacOS,
+            Platform::Ios,
+            Platform::Android,
+            Platform::Linux,
+        ];
+
+        // The index stores platforms by these names, and clients parse them back
+        for platform in known.iter() {
+            assert!(platform.is_known());
+            assert_eq!(&Platform::from(platform.as_str()), platform);
+            assert_eq!(platform.to_string(), platform.as_str());
+
+            let json = serde_json::to_string(platform).unwrap();
+            assert_eq!(json, format!("\"{}\"", platform.as_str()));
+            assert_eq!(&serde_json::from_str::<Platform>(&json).unwrap(), platform);
+        }
+
+        assert_eq!(Platform::from("macOS"), Platform::MacOS);
+        assert_eq!(Platform::from("darwin"), Platform::MacOS);
+        assert_eq!(
+            serde_json::to_string(&Platform::from("OSX")).unwrap(),
+            "\"macos\""
+        );
+    }
+
+    #[test]
+    fn unknown_values() {
+        let platform = Platform::from("Haiku");
+        assert_eq!(platform, Platform::Other("Haiku".into()));
+        assert!(!platform.is_known());
+        assert_eq!(platform.as_str(), "Haiku");
+        assert_ne!(platform, Platform::from("haiku"));
+
+        let platform: Platform = serde_json::from_str("\"freebsd\"").unwrap();
+        assert_eq!(platform, Platform::Other("freebsd".into()));
+        assert_eq!(serde_json::to_string(&platform).unwrap(), "\"freebsd\"");
+    }
+}
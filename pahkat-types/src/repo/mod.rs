@@ -2,8 +2,10 @@ mod url;
 
 pub use self::url::{RepoUrl, RepoUrlError};
 
-use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use crate::LangTagMap;
 use typed_builder::TypedBuilder;
 use ::url::Url;
 
@@ -55,11 +57,11 @@ pub struct Index {
 
     #[serde(default)]
     #[builder(default)]
-    pub name: BTreeMap<String, String>,
+    pub name: LangTagMap<String>,
 
     #[serde(default)]
     #[builder(default)]
-    pub description: BTreeMap<String, String>,
+    pub description: LangTagMap<String>,
 
     pub agent: Agent,
 }
@@ -75,13 +77,47 @@ pub struct Agent {
     pub url: Option<Url>,
 }
 
-/// This struct represents the strings for localising tags and is found in the
-/// `strings/` directory at the base of a Pahkat repository.
+#[derive(
+    Debug,
+    Serialize,
+    Deserialize,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    TypedBuilder,
+)]
+#[non_exhaustive]
+/// Translations of the tags and channels of a repository into one language.
 ///
-/// The TOML file this struct represents is named after the prefix of the given tag,
-/// such that a tag of `category:keyboards` would look up `strings/category.toml`.
+/// This struct represents a TOML file in the `strings/` directory at the base of a Pahkat
+/// repository, named after the BCP 47 tag of its language, such as `strings/se.toml`.
+/// Tags and channels without a translation are shown as is.
 pub struct Localisation {
-    // TODO
+    /// Labels keyed by the full tag, such as `category:keyboards`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[builder(default)]
+    pub tags: BTreeMap<String, String>,
+
+    /// Labels keyed by channel name, such as `nightly`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[builder(default)]
+    pub channels: BTreeMap<String, String>,
+}
+
+impl Localisation {
+    /// Adds the labels of `other` that are not already in this localisation.
+    pub fn merge_missing(&mut self, other: Localisation) {
+        for (key, value) in other.tags {
+            self.tags.entry(key).or_insert(value);
+        }
+        for (key, value) in other.channels {
+            self.channels.entry(key).or_insert(value);
+        }
+    }
 }
 
 #[derive(
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+/// Detects a package by the presence of a file or directory.
+///
+/// Relative paths are resolved against the install directory of the package store.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, TypedBuilder,
+)]
+pub struct FileRef {
+    #[builder(default = "FileRef".into())]
+    _type: String,
+
+    pub path: String,
+    /// A file whose contents are the installed version, resolved like `path`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    pub version_file: Option<String>,
+}
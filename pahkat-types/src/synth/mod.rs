@@ -1,10 +1,11 @@
+pub mod file;
 pub mod macos;
 pub mod windows;
 
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
-use crate::{DependencyMap, LangTagMap};
+use crate::{Arch, DependencyMap, LangTagMap, Platform};
 
 #[derive(
     Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, TypedBuilder,
@@ -39,9 +40,9 @@ pub struct Descriptor {
 )]
 #[non_exhaustive]
 pub struct Target {
-    pub platform: String,
+    pub platform: Platform,
     #[builder(default)]
-    pub arch: Option<String>,
+    pub arch: Option<Arch>,
     #[serde(default)]
     #[builder(default)]
     pub dependencies: DependencyMap,
@@ -67,4 +68,5 @@ pub enum Verifier {
     WindowsRegistryKey(windows::RegistryKey),
     MacOSPackageRef(macos::PackageRef),
     MacOSPathRef(macos::PathRef),
+    FileRef(file::FileRef),
 }